@@ -1,14 +1,14 @@
 extern crate pocket;
 
-use pocket::Pocket;
+use pocket::PocketAuth;
 use std::io;
 
 fn main() {
-    let mut pocket = Pocket::new(&*option_env!("POCKET_CONSUMER_KEY").unwrap(), None);
-    let url = pocket.get_auth_url().unwrap();
-    println!("Follow auth URL to provide access: {}", url);
+    let auth = PocketAuth::new(&*option_env!("POCKET_CONSUMER_KEY").unwrap());
+    let pending = auth.request_authorization().unwrap();
+    println!("Follow auth URL to provide access: {}", pending.auth_url);
     let _ = io::stdin().read_line(&mut String::new());
-    let username = pocket.authorize().unwrap();
+    let (mut pocket, username) = auth.authorize(&pending).unwrap();
     println!("username: {}", username);
     println!("access token: {:?}", pocket.access_token());
 
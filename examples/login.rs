@@ -0,0 +1,18 @@
+extern crate pocket;
+
+use pocket::{LoopbackServer, PocketAuth};
+use std::time::Duration;
+
+fn main() {
+    let auth = PocketAuth::new(&*option_env!("POCKET_CONSUMER_KEY").unwrap());
+    let server = LoopbackServer::bind()
+        .unwrap()
+        .timeout(Duration::from_secs(300));
+    let (pocket, username) = auth
+        .authorize_with_loopback(&server, |url| {
+            println!("Follow auth URL to provide access: {}", url)
+        })
+        .unwrap();
+    println!("username: {}", username);
+    println!("access token: {:?}", pocket.access_token());
+}
@@ -0,0 +1,242 @@
+use crate::{
+    percent_encode, post, Pocket, PocketAuthorizeRequest, PocketAuthorizeResponse, PocketError,
+    PocketOAuthRequest, PocketOAuthResponse, PocketResult, DEFAULT_BASE_URL,
+};
+use reqwest::Client;
+use rustc_serialize::{json, Decodable, Decoder, Encodable, Encoder};
+use url::Url;
+
+const REDIRECT_URI: &str = "rustapi:finishauth";
+
+// First half of the OAuth flow: only the consumer key is known.
+#[derive(Clone)]
+pub struct PocketAuth {
+    consumer_key: String,
+    redirect_uri: String,
+    base_url: String,
+    client: Client,
+}
+
+// Second half: the request token is waiting for the user to approve it.
+// It can be encoded and stored until the user comes back.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PendingAuthorization {
+    pub request_token: String,
+    pub auth_url: Url,
+    pub state: Option<String>,
+}
+
+impl PendingAuthorization {
+    pub fn new(request_token: &str, redirect_uri: &str) -> PocketResult<PendingAuthorization> {
+        let mut url = Url::parse("https://getpocket.com/auth/authorize")?;
+        url.set_query_from_pairs(
+            vec![
+                ("request_token", request_token),
+                ("redirect_uri", redirect_uri),
+            ]
+            .into_iter(),
+        );
+        Ok(PendingAuthorization {
+            request_token: request_token.to_string(),
+            auth_url: url,
+            state: None,
+        })
+    }
+
+    // Checks the state a redirect handler got back against the one this flow was started with.
+    pub fn verify_state(&self, state: &str) -> PocketResult<()> {
+        check_state(self.state.as_ref().map(|v| &**v), Some(state))
+    }
+}
+
+fn check_state(expected: Option<&str>, received: Option<&str>) -> PocketResult<()> {
+    match (expected, received) {
+        (None, _) => Ok(()),
+        // compare in constant time, the state is a secret
+        (Some(a), Some(b))
+            if a.len() == b.len()
+                && a.bytes()
+                    .zip(b.bytes())
+                    .fold(0, |acc, (x, y)| acc | (x ^ y))
+                    == 0 =>
+        {
+            Ok(())
+        }
+        _ => Err(PocketError::StateMismatch),
+    }
+}
+
+impl Encodable for PendingAuthorization {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        e.emit_struct("PendingAuthorization", 3, |e| {
+            e.emit_struct_field("request_token", 0, |e| self.request_token.encode(e))?;
+            e.emit_struct_field("auth_url", 1, |e| self.auth_url.to_string().encode(e))?;
+            e.emit_struct_field("state", 2, |e| self.state.encode(e))
+        })
+    }
+}
+
+impl Decodable for PendingAuthorization {
+    fn decode<D: Decoder>(d: &mut D) -> Result<PendingAuthorization, D::Error> {
+        d.read_struct("PendingAuthorization", 3, |d| {
+            let request_token = d.read_struct_field("request_token", 0, |d| d.read_str())?;
+            let auth_url = d.read_struct_field("auth_url", 1, |d| d.read_str())?;
+            Ok(PendingAuthorization {
+                request_token: request_token,
+                auth_url: Url::parse(&*auth_url).map_err(|e| d.error(&*e.to_string()))?,
+                state: d.read_struct_field("state", 2, Decodable::decode)?,
+            })
+        })
+    }
+}
+
+impl PocketAuth {
+    pub fn new(consumer_key: &str) -> PocketAuth {
+        PocketAuth {
+            consumer_key: consumer_key.to_string(),
+            redirect_uri: REDIRECT_URI.to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: Client::new(),
+        }
+    }
+
+    // The server used for the API calls, also passed to the authorized client.
+    pub fn base_url(mut self, base_url: &str) -> PocketAuth {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn redirect_uri(mut self, redirect_uri: &str) -> PocketAuth {
+        self.redirect_uri = redirect_uri.to_string();
+        self
+    }
+
+    #[inline]
+    pub fn consumer_key(&self) -> &str {
+        &*self.consumer_key
+    }
+
+    #[inline]
+    pub fn request_authorization(&self) -> PocketResult<PendingAuthorization> {
+        self.request_token(None)
+    }
+
+    // The state is sent along with the request and must be echoed back by the server.
+    #[inline]
+    pub fn request_authorization_with_state(
+        &self,
+        state: &str,
+    ) -> PocketResult<PendingAuthorization> {
+        self.request_token(Some(state))
+    }
+
+    // Pocket doesn't pass the state on to the redirect, so it is added
+    // to the redirect URI for the redirect handler to check.
+    fn request_token(&self, state: Option<&str>) -> PocketResult<PendingAuthorization> {
+        Url::parse(&*self.redirect_uri)?;
+        let redirect_uri = match state {
+            Some(state) => format!(
+                "{}{}state={}",
+                self.redirect_uri,
+                if self.redirect_uri.contains('?') {
+                    '&'
+                } else {
+                    '?'
+                },
+                percent_encode(state)
+            ),
+            None => self.redirect_uri.clone(),
+        };
+        let request = json::encode(&PocketOAuthRequest {
+            consumer_key: &*self.consumer_key,
+            redirect_uri: &*redirect_uri,
+            state: state,
+        })?;
+
+        post(
+            &self.client,
+            false,
+            &*format!("{}/v3/oauth/request", self.base_url),
+            &*request,
+        )
+        .and_then(|r: PocketOAuthResponse| {
+            check_state(state, r.state.as_ref().map(|v| &**v))?;
+            let mut pending = PendingAuthorization::new(&*r.code, &*redirect_uri)?;
+            pending.state = r.state;
+            Ok(pending)
+        })
+    }
+
+    // Returns the authenticated client along with the username.
+    pub fn authorize(&self, pending: &PendingAuthorization) -> PocketResult<(Pocket, String)> {
+        if pending.request_token.is_empty() {
+            return Err(PocketError::NoRequestToken);
+        }
+
+        let request = json::encode(&PocketAuthorizeRequest {
+            consumer_key: &*self.consumer_key,
+            code: &*pending.request_token,
+        })?;
+
+        post(
+            &self.client,
+            false,
+            &*format!("{}/v3/oauth/authorize", self.base_url),
+            &*request,
+        )
+        .and_then(|r: PocketAuthorizeResponse| {
+            // the state is only echoed back by some servers
+            if let Some(ref state) = r.state {
+                check_state(pending.state.as_ref().map(|v| &**v), Some(state))?;
+            }
+            let mut pocket =
+                Pocket::with_client(&*self.consumer_key, &*r.access_token, self.client.clone());
+            pocket.set_base_url(&*self.base_url);
+            Ok((pocket, r.username))
+        })
+    }
+}
+
+#[test]
+fn test_pending_authorization() {
+    let pending = PendingAuthorization::new("abc-123", REDIRECT_URI).unwrap();
+    assert_eq!(
+        pending.auth_url.to_string(),
+        "https://getpocket.com/auth/authorize?request_token=abc-123&redirect_uri=rustapi%3Afinishauth"
+    );
+
+    let encoded = json::encode(&pending).unwrap();
+    assert_eq!(
+        json::decode::<PendingAuthorization>(&*encoded).unwrap(),
+        pending
+    );
+
+    let pending = PendingAuthorization {
+        request_token: String::new(),
+        auth_url: pending.auth_url,
+        state: None,
+    };
+    match PocketAuth::new("key").authorize(&pending) {
+        Err(PocketError::NoRequestToken) => (),
+        r => panic!("unexpected result: {:?}", r.map(|v| v.1)),
+    }
+}
+
+#[test]
+fn test_state_verification() {
+    let mut pending = PendingAuthorization::new("abc-123", "https://example.com/done").unwrap();
+    assert!(pending.verify_state("anything").is_ok());
+
+    pending.state = Some("s3cr3t".to_string());
+    assert!(pending.verify_state("s3cr3t").is_ok());
+    assert!(pending.verify_state("s3cr3").is_err());
+    assert!(pending.verify_state("s3cr3T").is_err());
+
+    assert!(check_state(Some("s3cr3t"), None).is_err());
+    assert!(check_state(None, Some("s3cr3t")).is_ok());
+
+    let decoded: PendingAuthorization =
+        json::decode(r#"{"request_token": "abc-123", "auth_url": "https://getpocket.com/"}"#)
+            .unwrap();
+    assert_eq!(decoded.state, None);
+}
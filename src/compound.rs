@@ -0,0 +1,272 @@
+use crate::search::url_host;
+use crate::{PocketGetSort, PocketGetTag, PocketItem, PocketResult, Query, Tag};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Not;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TagFilter {
+    Tag(PocketGetTag),
+    All(Vec<TagFilter>),
+    Any(Vec<TagFilter>),
+    Not(Box<TagFilter>),
+}
+
+impl TagFilter {
+    pub fn tagged(tag: Tag) -> TagFilter {
+        TagFilter::Tag(PocketGetTag::Tagged(tag))
+    }
+
+    pub fn untagged() -> TagFilter {
+        TagFilter::Tag(PocketGetTag::Untagged)
+    }
+
+    pub fn all<I: IntoIterator<Item = TagFilter>>(filters: I) -> TagFilter {
+        TagFilter::All(filters.into_iter().collect())
+    }
+
+    pub fn any<I: IntoIterator<Item = TagFilter>>(filters: I) -> TagFilter {
+        TagFilter::Any(filters.into_iter().collect())
+    }
+
+    fn collect_tags(&self, tags: &mut Vec<PocketGetTag>) {
+        match *self {
+            TagFilter::Tag(ref tag) => {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+            TagFilter::All(ref filters) | TagFilter::Any(ref filters) => {
+                for filter in filters {
+                    filter.collect_tags(tags);
+                }
+            }
+            TagFilter::Not(ref filter) => filter.collect_tags(tags),
+        }
+    }
+
+    fn eval(&self, results: &[(PocketGetTag, BTreeSet<u64>)]) -> ItemSet {
+        match *self {
+            TagFilter::Tag(ref tag) => ItemSet::Included(
+                results
+                    .iter()
+                    .find(|r| r.0 == *tag)
+                    .map(|r| r.1.clone())
+                    .unwrap_or_default(),
+            ),
+            TagFilter::All(ref filters) => filters
+                .iter()
+                .map(|f| f.eval(results))
+                .fold(ItemSet::Excluded(BTreeSet::new()), ItemSet::intersect),
+            TagFilter::Any(ref filters) => filters
+                .iter()
+                .map(|f| f.eval(results))
+                .fold(ItemSet::Included(BTreeSet::new()), ItemSet::union),
+            TagFilter::Not(ref filter) => filter.eval(results).complement(),
+        }
+    }
+}
+
+impl Not for TagFilter {
+    type Output = TagFilter;
+
+    fn not(self) -> TagFilter {
+        TagFilter::Not(Box::new(self))
+    }
+}
+
+// A set of item ids, or the complement of one.
+enum ItemSet {
+    Included(BTreeSet<u64>),
+    Excluded(BTreeSet<u64>),
+}
+
+impl ItemSet {
+    fn complement(self) -> ItemSet {
+        match self {
+            ItemSet::Included(s) => ItemSet::Excluded(s),
+            ItemSet::Excluded(s) => ItemSet::Included(s),
+        }
+    }
+
+    fn intersect(self, other: ItemSet) -> ItemSet {
+        match (self, other) {
+            (ItemSet::Included(a), ItemSet::Included(b)) => {
+                ItemSet::Included(a.intersection(&b).cloned().collect())
+            }
+            (ItemSet::Included(a), ItemSet::Excluded(b))
+            | (ItemSet::Excluded(b), ItemSet::Included(a)) => {
+                ItemSet::Included(a.difference(&b).cloned().collect())
+            }
+            (ItemSet::Excluded(a), ItemSet::Excluded(b)) => {
+                ItemSet::Excluded(a.union(&b).cloned().collect())
+            }
+        }
+    }
+
+    fn union(self, other: ItemSet) -> ItemSet {
+        self.complement().intersect(other.complement()).complement()
+    }
+}
+
+pub(crate) fn run<F>(
+    query: &Query,
+    filter: &TagFilter,
+    mut fetch: F,
+) -> PocketResult<Vec<PocketItem>>
+where
+    F: FnMut(Query) -> PocketResult<Vec<PocketItem>>,
+{
+    // the query's own tag is one more term for the items to match
+    let filter = match query.tag {
+        Some(ref tag) => TagFilter::all(vec![TagFilter::Tag(tag.clone()), filter.clone()]),
+        None => filter.clone(),
+    };
+    let mut tags = Vec::new();
+    filter.collect_tags(&mut tags);
+    // every call needs all the matching items, the slice is taken from the merged list
+    let mut base = query.clone();
+    base.count = None;
+    base.offset = None;
+
+    let mut items = BTreeMap::new();
+    let mut results = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let mut query = base.clone();
+        query.tag(tag.clone());
+        let ids = fetch(query)?
+            .into_iter()
+            .map(|item| {
+                let id = item.item_id;
+                items.insert(id, item);
+                id
+            })
+            .collect();
+        results.push((tag, ids));
+    }
+
+    let ids = match filter.eval(&results) {
+        ItemSet::Included(ids) => ids,
+        // only negated terms left, so subtract them from the whole list
+        ItemSet::Excluded(ids) => {
+            let mut query = base.clone();
+            query.tag = None;
+            fetch(query)?
+                .into_iter()
+                .filter(|item| !ids.contains(&item.item_id))
+                .map(|item| {
+                    let id = item.item_id;
+                    items.insert(id, item);
+                    id
+                })
+                .collect()
+        }
+    };
+
+    let mut list: Vec<PocketItem> = ids.into_iter().filter_map(|id| items.remove(&id)).collect();
+    sort_items(&mut list, query.sort);
+    Ok(list
+        .into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.count.unwrap_or(usize::MAX))
+        .collect())
+}
+
+fn sort_items(items: &mut Vec<PocketItem>, sort: Option<PocketGetSort>) {
+    match sort.unwrap_or(PocketGetSort::Newest) {
+        PocketGetSort::Newest => items.sort_by(|a, b| b.time_added.cmp(&a.time_added)),
+        PocketGetSort::Oldest => items.sort_by(|a, b| a.time_added.cmp(&b.time_added)),
+        PocketGetSort::Title => items.sort_by_key(|item| {
+            if item.resolved_title.is_empty() {
+                item.given_title.to_lowercase()
+            } else {
+                item.resolved_title.to_lowercase()
+            }
+        }),
+        PocketGetSort::Site => items.sort_by_key(|item| url_host(&item.given_url).to_lowercase()),
+    }
+}
+
+#[cfg(test)]
+fn test_item(item_id: u64, time_added: i64) -> PocketItem {
+    let mut item: PocketItem = rustc_serialize::json::decode(crate::ITEM_JSON).unwrap();
+    item.item_id = item_id;
+    item.time_added = time::Timespec::new(time_added, 0);
+    item
+}
+
+#[test]
+fn test_compound_filter() {
+    let (rust, tokio, read_later) = (
+        Tag::new("rust").unwrap(),
+        Tag::new("tokio").unwrap(),
+        Tag::new("read-later").unwrap(),
+    );
+    let mut calls = Vec::new();
+    let mut fetch = |query: Query| {
+        assert_eq!((query.count, query.offset), (None, None));
+        calls.push(query.tag.clone());
+        Ok(match query.tag {
+            Some(PocketGetTag::Tagged(ref tag)) if *tag == rust => {
+                vec![test_item(1, 10), test_item(2, 20), test_item(3, 30)]
+            }
+            Some(PocketGetTag::Tagged(ref tag)) if *tag == tokio => {
+                vec![test_item(2, 20), test_item(3, 30), test_item(4, 40)]
+            }
+            Some(PocketGetTag::Tagged(ref tag)) if *tag == read_later => vec![test_item(3, 30)],
+            _ => (1..6).map(|id| test_item(id, id as i64 * 10)).collect(),
+        })
+    };
+    let ids = |items: Vec<PocketItem>| items.iter().map(|i| i.item_id).collect::<Vec<_>>();
+
+    let mut query = Query::new();
+    query.sort_by_oldest();
+
+    let filter = TagFilter::all(vec![
+        TagFilter::tagged(rust.clone()),
+        TagFilter::tagged(tokio.clone()),
+        !TagFilter::tagged(read_later.clone()),
+    ]);
+    assert_eq!(ids(run(&query, &filter, &mut fetch).unwrap()), vec![2]);
+
+    let filter = TagFilter::any(vec![
+        TagFilter::tagged(rust.clone()),
+        TagFilter::tagged(tokio.clone()),
+    ]);
+    assert_eq!(
+        ids(run(&query, &filter, &mut fetch).unwrap()),
+        vec![1, 2, 3, 4]
+    );
+
+    query.sort_by_newest();
+    let filter = !TagFilter::tagged(rust.clone());
+    assert_eq!(ids(run(&query, &filter, &mut fetch).unwrap()), vec![5, 4]);
+
+    query.tag(PocketGetTag::Tagged(tokio.clone()));
+    assert_eq!(ids(run(&query, &filter, &mut fetch).unwrap()), vec![4]);
+
+    let mut query = Query::new();
+    query.sort_by_oldest().slice(1, 2);
+    let filter = TagFilter::any(vec![
+        TagFilter::tagged(rust.clone()),
+        TagFilter::tagged(tokio.clone()),
+    ]);
+    assert_eq!(ids(run(&query, &filter, &mut fetch).unwrap()), vec![2, 3]);
+
+    drop(fetch);
+    assert_eq!(
+        calls,
+        vec![
+            Some(PocketGetTag::Tagged(rust.clone())),
+            Some(PocketGetTag::Tagged(tokio.clone())),
+            Some(PocketGetTag::Tagged(read_later)),
+            Some(PocketGetTag::Tagged(rust.clone())),
+            Some(PocketGetTag::Tagged(tokio.clone())),
+            Some(PocketGetTag::Tagged(rust.clone())),
+            None,
+            Some(PocketGetTag::Tagged(tokio.clone())),
+            Some(PocketGetTag::Tagged(rust.clone())),
+            Some(PocketGetTag::Tagged(rust)),
+            Some(PocketGetTag::Tagged(tokio)),
+        ]
+    );
+}
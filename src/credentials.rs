@@ -0,0 +1,343 @@
+#[cfg(feature = "vault")]
+use crate::vault::VaultKey;
+use crate::{Pocket, PocketError, PocketResult};
+use rustc_serialize::json::{self, Json};
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Clone, RustcEncodable, RustcDecodable)]
+pub struct Credentials {
+    pub consumer_key: String,
+    pub access_token: String,
+    pub username: Option<String>,
+}
+
+impl Credentials {
+    pub fn new(consumer_key: &str, access_token: &str) -> Credentials {
+        Credentials {
+            consumer_key: consumer_key.to_string(),
+            access_token: access_token.to_string(),
+            username: None,
+        }
+    }
+
+    pub fn username(mut self, username: &str) -> Credentials {
+        self.username = Some(username.to_string());
+        self
+    }
+
+    #[inline]
+    pub fn pocket(&self) -> Pocket {
+        Pocket::new(&*self.consumer_key, &*self.access_token)
+    }
+}
+
+impl<'a> From<&'a Pocket> for Credentials {
+    fn from(pocket: &'a Pocket) -> Credentials {
+        Credentials::new(pocket.consumer_key(), pocket.access_token())
+    }
+}
+
+#[derive(RustcEncodable, RustcDecodable)]
+struct CredentialsFile {
+    accounts: BTreeMap<String, Credentials>,
+}
+
+// Named accounts kept in a JSON file only readable by its owner,
+// optionally sealed with a passphrase.
+pub struct CredentialStore {
+    path: PathBuf,
+    accounts: BTreeMap<String, Credentials>,
+    #[cfg(feature = "vault")]
+    key: Option<VaultKey>,
+}
+
+impl CredentialStore {
+    // A missing file gives an empty store, it is created on save.
+    pub fn open<P: AsRef<Path>>(path: P) -> PocketResult<CredentialStore> {
+        let path = path.as_ref().to_path_buf();
+        let accounts = match read_file(&path)? {
+            Some(data) => match json::decode::<CredentialsFile>(&*data) {
+                Ok(file) => file.accounts,
+                Err(_) if is_encrypted_file(&*data) => {
+                    return Err(PocketError::Vault(
+                        "encrypted store, open it with a passphrase".to_string(),
+                    ))
+                }
+                Err(e) => return Err(e.into()),
+            },
+            None => BTreeMap::new(),
+        };
+
+        Ok(CredentialStore {
+            path: path,
+            accounts: accounts,
+            #[cfg(feature = "vault")]
+            key: None,
+        })
+    }
+
+    // Like `open`, but the file is encrypted with a key derived from the passphrase.
+    #[cfg(feature = "vault")]
+    pub fn open_encrypted<P: AsRef<Path>>(
+        path: P,
+        passphrase: &str,
+    ) -> PocketResult<CredentialStore> {
+        let path = path.as_ref().to_path_buf();
+        let (key, accounts) = match read_file(&path)? {
+            Some(data) => {
+                let (key, plaintext) = VaultKey::unseal(&*data, passphrase)?;
+                let plaintext =
+                    String::from_utf8(plaintext).map_err(|e| PocketError::Vault(e.to_string()))?;
+                (key, json::decode::<CredentialsFile>(&*plaintext)?.accounts)
+            }
+            None => (VaultKey::new(passphrase)?, BTreeMap::new()),
+        };
+
+        Ok(CredentialStore {
+            path: path,
+            accounts: accounts,
+            key: Some(key),
+        })
+    }
+
+    // Re-encrypts the store with a new passphrase, also encrypting a plain store.
+    #[cfg(feature = "vault")]
+    pub fn change_passphrase(&mut self, passphrase: &str) -> PocketResult<()> {
+        self.key = Some(VaultKey::new(passphrase)?);
+        self.save()
+    }
+
+    #[cfg(feature = "vault")]
+    #[inline]
+    pub fn is_encrypted(&self) -> bool {
+        self.key.is_some()
+    }
+
+    // Opens `$XDG_CONFIG_HOME/pocket/<profile>.json`, or `~/.config/pocket/<profile>.json`.
+    pub fn open_profile(profile: &str) -> PocketResult<CredentialStore> {
+        let dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .ok_or_else(|| PocketError::MissingEnvVar("HOME".to_string()))?;
+        CredentialStore::open(dir.join("pocket").join(format!("{}.json", profile)))
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &*self.path
+    }
+
+    #[inline]
+    pub fn get(&self, account: &str) -> Option<&Credentials> {
+        self.accounts.get(account)
+    }
+
+    #[inline]
+    pub fn insert(&mut self, account: &str, credentials: Credentials) -> Option<Credentials> {
+        self.accounts.insert(account.to_string(), credentials)
+    }
+
+    #[inline]
+    pub fn remove(&mut self, account: &str) -> Option<Credentials> {
+        self.accounts.remove(account)
+    }
+
+    #[inline]
+    pub fn accounts(&self) -> btree_map::Keys<String, Credentials> {
+        self.accounts.keys()
+    }
+
+    pub fn pocket(&self, account: &str) -> PocketResult<Pocket> {
+        self.get(account)
+            .map(Credentials::pocket)
+            .ok_or_else(|| PocketError::UnknownAccount(account.to_string()))
+    }
+
+    // Writes a temporary file next to the store and moves it into place.
+    pub fn save(&self) -> PocketResult<()> {
+        let data = json::encode(&CredentialsFile {
+            accounts: self.accounts.clone(),
+        })?;
+        #[cfg(feature = "vault")]
+        let data = match self.key {
+            Some(ref key) => key.seal(data.as_bytes())?,
+            None => data,
+        };
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        {
+            let mut options = OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            let mut file = options.open(&tmp)?;
+            #[cfg(unix)]
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+            file.write_all(data.as_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp, &self.path).map_err(From::from)
+    }
+}
+
+fn is_encrypted_file(data: &str) -> bool {
+    Json::from_str(data)
+        .ok()
+        .map_or(false, |json| json.find("ciphertext").is_some())
+}
+
+fn read_file(path: &Path) -> PocketResult<Option<String>> {
+    match File::open(path) {
+        Ok(mut file) => {
+            check_permissions(path, &file)?;
+            let mut data = String::new();
+            file.read_to_string(&mut data)?;
+            Ok(Some(data))
+        }
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(unix)]
+fn check_permissions(path: &Path, file: &File) -> PocketResult<()> {
+    // group or world access
+    if file.metadata()?.permissions().mode() & 0o077 != 0 {
+        Err(PocketError::InsecureFile(path.to_path_buf()))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_path: &Path, _file: &File) -> PocketResult<()> {
+    Ok(())
+}
+
+impl Pocket {
+    // Reads `POCKET_CONSUMER_KEY` and `POCKET_ACCESS_TOKEN`.
+    pub fn from_env() -> PocketResult<Pocket> {
+        let var = |name: &str| {
+            env::var(name)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| PocketError::MissingEnvVar(name.to_string()))
+        };
+        Ok(Pocket::new(
+            &*var("POCKET_CONSUMER_KEY")?,
+            &*var("POCKET_ACCESS_TOKEN")?,
+        ))
+    }
+}
+
+#[test]
+fn test_credential_store() {
+    let path = env::temp_dir().join(format!("pocket-credentials-{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut store = CredentialStore::open(&path).unwrap();
+    assert_eq!(store.accounts().count(), 0);
+    store.insert(
+        "work",
+        Credentials::new("key", "work-token").username("jane"),
+    );
+    store.insert("home", Credentials::from(&Pocket::new("key", "home-token")));
+    store.save().unwrap();
+
+    let store = CredentialStore::open(&path).unwrap();
+    assert_eq!(store.accounts().collect::<Vec<_>>(), vec!["home", "work"]);
+    assert_eq!(
+        store.get("work").unwrap().username,
+        Some("jane".to_string())
+    );
+    assert_eq!(store.pocket("home").unwrap().access_token(), "home-token");
+    match store.pocket("play") {
+        Err(PocketError::UnknownAccount(ref name)) => assert_eq!(name, "play"),
+        r => panic!(
+            "unexpected result: {:?}",
+            r.map(|p| p.access_token().to_string())
+        ),
+    }
+
+    #[cfg(unix)]
+    {
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        for &mode in &[0o644, 0o640] {
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+            match CredentialStore::open(&path) {
+                Err(PocketError::InsecureFile(ref p)) => assert_eq!(*p, path),
+                _ => panic!("file with mode {:o} was accepted", mode),
+            }
+        }
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "vault")]
+#[test]
+fn test_encrypted_credential_store() {
+    let path = env::temp_dir().join(format!("pocket-vault-{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut store = CredentialStore::open_encrypted(&path, "correct horse").unwrap();
+    assert!(store.is_encrypted());
+    store.insert(
+        "work",
+        Credentials::new("key", "s3cr3t-token").username("jane"),
+    );
+    store.save().unwrap();
+
+    let data = fs::read_to_string(&path).unwrap();
+    assert!(!data.contains("s3cr3t-token") && !data.contains("jane"));
+    match CredentialStore::open_encrypted(&path, "battery staple") {
+        Err(PocketError::WrongPassphrase) => (),
+        _ => panic!("wrong passphrase was accepted"),
+    }
+    match CredentialStore::open(&path) {
+        Err(PocketError::Vault(ref msg)) if msg.contains("encrypted store") => (),
+        _ => panic!("encrypted store was opened as plaintext"),
+    }
+
+    let mut json = Json::from_str(&*data).unwrap();
+    json.as_object_mut()
+        .unwrap()
+        .insert("m_cost".to_string(), Json::U64(u32::max_value() as u64));
+    let expensive = env::temp_dir().join(format!("pocket-vault-m-{}.json", std::process::id()));
+    fs::write(&expensive, json.to_string()).unwrap();
+    #[cfg(unix)]
+    fs::set_permissions(&expensive, fs::Permissions::from_mode(0o600)).unwrap();
+    match CredentialStore::open_encrypted(&expensive, "correct horse") {
+        Err(PocketError::Vault(ref msg)) if msg == "vault parameters out of range" => (),
+        _ => panic!("out of range vault parameters were accepted"),
+    }
+    fs::write(&expensive, r#"{"accounts":{}}"#).unwrap();
+    match CredentialStore::open_encrypted(&expensive, "correct horse") {
+        Err(PocketError::Vault(ref msg)) if msg == "not an encrypted store" => (),
+        _ => panic!("plaintext store was opened as encrypted"),
+    }
+    fs::remove_file(&expensive).unwrap();
+
+    let mut store = CredentialStore::open_encrypted(&path, "correct horse").unwrap();
+    assert_eq!(
+        store.get("work").unwrap().username,
+        Some("jane".to_string())
+    );
+    store.change_passphrase("battery staple").unwrap();
+    assert!(CredentialStore::open_encrypted(&path, "correct horse").is_err());
+
+    let store = CredentialStore::open_encrypted(&path, "battery staple").unwrap();
+    assert_eq!(store.pocket("work").unwrap().access_token(), "s3cr3t-token");
+    fs::remove_file(&path).unwrap();
+}
@@ -1,10 +1,43 @@
-use reqwest::Client;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, StatusCode};
+use rustc_serialize::json::Json;
 use rustc_serialize::{json, Decodable, Decoder, Encodable, Encoder};
-use std::convert::From;
+use std::cell::Cell;
+use std::cmp;
+use std::collections::btree_set;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeSet;
+use std::convert::{From, TryFrom};
 use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
 use std::io::Read;
+use std::iter::FromIterator;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::vec;
 use time::Timespec;
-use url::Url;
+
+mod auth;
+mod compound;
+mod credentials;
+#[cfg(feature = "loopback")]
+mod loopback;
+mod ratelimit;
+mod retry;
+mod search;
+#[cfg(feature = "vault")]
+mod vault;
+
+pub use crate::auth::{PendingAuthorization, PocketAuth};
+pub use crate::compound::TagFilter;
+pub use crate::credentials::{CredentialStore, Credentials};
+#[cfg(feature = "loopback")]
+pub use crate::loopback::LoopbackServer;
+pub use crate::ratelimit::{RateLimit, RateLimits, Throttle};
+pub use crate::retry::{is_transient, Clock, RetryPolicy, SystemClock};
+pub use crate::search::Search;
 
 pub trait JsonEncodable {
     fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError>;
@@ -21,12 +54,26 @@ impl<T: Encodable> JsonEncodable for T {
 }
 
 macro_rules! impl_item_pocket_action {
-    ($name:expr, $cls:ident) => {
+    ($name:expr, $cls:ident, $variant:ident) => {
         pub struct $cls {
             item_id: u64,
             time: Option<u64>,
         }
 
+        impl $cls {
+            pub fn new(item_id: u64) -> $cls {
+                $cls {
+                    item_id: item_id,
+                    time: None,
+                }
+            }
+
+            pub fn at(mut self, time: u64) -> $cls {
+                self.time = Some(time);
+                self
+            }
+        }
+
         impl PocketAction for $cls {
             fn name(&self) -> &'static str {
                 $name
@@ -35,34 +82,230 @@ macro_rules! impl_item_pocket_action {
 
         impl JsonEncodable for $cls {
             fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
-                e.emit_struct(stringify!($cls), 3, |e| {
-                    e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))
-                        .and_then(|_| {
-                            e.emit_struct_field("item_id", 1, |e| e.emit_u64(self.item_id))
-                        })
-                        .and_then(|_| {
-                            e.emit_struct_field("time", 2, |e| match self.time {
-                                Some(v) => e.emit_option_some(|e| e.emit_u64(v)),
-                                None => e.emit_option_none(),
-                            })
-                        })
-                })
+                Action::from(self).encode(e)
+            }
+        }
+
+        impl<'a> From<&'a $cls> for Action {
+            fn from(action: &'a $cls) -> Action {
+                Action::$variant {
+                    item_id: action.item_id,
+                    time: action.time,
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_item_tags_pocket_action {
+    ($name:expr, $cls:ident, $variant:ident) => {
+        pub struct $cls<'a> {
+            item_id: u64,
+            tags: &'a TagSet,
+            time: Option<u64>,
+        }
+
+        impl<'a> $cls<'a> {
+            pub fn new(item_id: u64, tags: &'a TagSet) -> $cls<'a> {
+                $cls {
+                    item_id: item_id,
+                    tags: tags,
+                    time: None,
+                }
+            }
+
+            pub fn at(mut self, time: u64) -> $cls<'a> {
+                self.time = Some(time);
+                self
+            }
+        }
+
+        impl<'a> PocketAction for $cls<'a> {
+            fn name(&self) -> &'static str {
+                $name
+            }
+        }
+
+        impl<'a> JsonEncodable for $cls<'a> {
+            fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
+                Action::from(self).encode(e)
+            }
+        }
+
+        impl<'a, 'b> From<&'b $cls<'a>> for Action {
+            fn from(action: &'b $cls<'a>) -> Action {
+                Action::$variant {
+                    item_id: action.item_id,
+                    tags: action.tags.clone(),
+                    time: action.time,
+                }
             }
         }
     };
 }
 
+// Errors reported by the API with the `X-Error-Code` header
+// (or implied by the HTTP status, or by a failed action without a code).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PocketApiError {
+    InvalidAccessToken,
+    MissingConsumerKey,
+    MissingRedirectUri,
+    InvalidConsumerKey,
+    UserRejectedCode,
+    AlreadyUsedCode,
+    InvalidRedirectUri,
+    MissingCode,
+    CodeNotFound,
+    ServerError,
+    Maintenance,
+    RateLimited,
+    Status(u16),
+    ActionFailed,
+    Unknown(u16),
+}
+
+impl PocketApiError {
+    pub fn from_code(code: u16) -> PocketApiError {
+        match code {
+            107 => PocketApiError::InvalidAccessToken,
+            138 => PocketApiError::MissingConsumerKey,
+            140 => PocketApiError::MissingRedirectUri,
+            152 => PocketApiError::InvalidConsumerKey,
+            158 => PocketApiError::UserRejectedCode,
+            159 => PocketApiError::AlreadyUsedCode,
+            181 => PocketApiError::InvalidRedirectUri,
+            182 => PocketApiError::MissingCode,
+            185 => PocketApiError::CodeNotFound,
+            199 => PocketApiError::ServerError,
+            code => PocketApiError::Unknown(code),
+        }
+    }
+
+    // Errors derived from the HTTP status or from a failed action have no code.
+    pub fn code(&self) -> Option<u16> {
+        match *self {
+            PocketApiError::InvalidAccessToken => Some(107),
+            PocketApiError::MissingConsumerKey => Some(138),
+            PocketApiError::MissingRedirectUri => Some(140),
+            PocketApiError::InvalidConsumerKey => Some(152),
+            PocketApiError::UserRejectedCode => Some(158),
+            PocketApiError::AlreadyUsedCode => Some(159),
+            PocketApiError::InvalidRedirectUri => Some(181),
+            PocketApiError::MissingCode => Some(182),
+            PocketApiError::CodeNotFound => Some(185),
+            PocketApiError::ServerError => Some(199),
+            PocketApiError::Maintenance
+            | PocketApiError::RateLimited
+            | PocketApiError::Status(_)
+            | PocketApiError::ActionFailed => None,
+            PocketApiError::Unknown(code) => Some(code),
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            PocketApiError::ServerError
+            | PocketApiError::Maintenance
+            | PocketApiError::RateLimited
+            | PocketApiError::Status(500)
+            | PocketApiError::Status(502)
+            | PocketApiError::Status(504) => true,
+            _ => false,
+        }
+    }
+
+    // Errors fixed by (re)authorizing or by a correct consumer key.
+    pub fn is_auth_error(&self) -> bool {
+        match *self {
+            PocketApiError::InvalidAccessToken
+            | PocketApiError::MissingConsumerKey
+            | PocketApiError::InvalidConsumerKey
+            | PocketApiError::UserRejectedCode
+            | PocketApiError::AlreadyUsedCode
+            | PocketApiError::MissingCode
+            | PocketApiError::CodeNotFound => true,
+            _ => false,
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match *self {
+            PocketApiError::InvalidAccessToken => "invalid or expired access token",
+            PocketApiError::MissingConsumerKey => "missing consumer key",
+            PocketApiError::MissingRedirectUri => "missing redirect uri",
+            PocketApiError::InvalidConsumerKey => "invalid consumer key",
+            PocketApiError::UserRejectedCode => "user rejected code",
+            PocketApiError::AlreadyUsedCode => "already used code",
+            PocketApiError::InvalidRedirectUri => "invalid redirect uri",
+            PocketApiError::MissingCode => "missing code",
+            PocketApiError::CodeNotFound => "code not found",
+            PocketApiError::ServerError => "Pocket server issue",
+            PocketApiError::Maintenance => "Pocket is down for maintenance",
+            PocketApiError::RateLimited => "rate limit exceeded",
+            PocketApiError::Status(..) => "unexpected HTTP status",
+            PocketApiError::ActionFailed => "action failed",
+            PocketApiError::Unknown(..) => "unknown error",
+        }
+    }
+}
+
+impl fmt::Display for PocketApiError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match (*self, self.code()) {
+            (PocketApiError::Status(status), _) => write!(fmt, "{} {}", self.description(), status),
+            (_, Some(code)) => write!(fmt, "{} (code {})", self.description(), code),
+            (_, None) => fmt.write_str(self.description()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum PocketError {
     Http(reqwest::Error),
     Io(std::io::Error),
     Json(json::DecoderError),
     Format(json::EncoderError),
-    Proto(u16, String),
+    Proto(PocketApiError, String),
+    InvalidTag(String),
+    InvalidSearch(String),
+    NotAuthenticated,
+    NoRequestToken,
+    StateMismatch,
+    MalformedHeader(String),
+    MissingEnvVar(String),
+    UnknownAccount(String),
+    InsecureFile(std::path::PathBuf),
+    WrongPassphrase,
+    Vault(String),
+    // Either the throttle refused to send a request, or the server refused it.
+    // The time until the quota is reset is unknown if the server didn't send it.
+    RateLimited { reset_in: Option<Duration> },
+    Url(url::ParseError),
 }
 
 pub type PocketResult<T> = Result<T, PocketError>;
 
+impl PocketError {
+    // Rate limits are reported as `RateLimited` to carry the reset time,
+    // but classify like the other API errors.
+    pub fn api_error(&self) -> Option<PocketApiError> {
+        match *self {
+            PocketError::Proto(err, _) => Some(err),
+            PocketError::RateLimited { .. } => Some(PocketApiError::RateLimited),
+            _ => None,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.api_error().map_or(false, |err| err.is_retryable())
+    }
+
+    pub fn is_auth_error(&self) -> bool {
+        self.api_error().map_or(false, |err| err.is_auth_error())
+    }
+}
+
 impl From<json::EncoderError> for PocketError {
     fn from(err: json::EncoderError) -> PocketError {
         PocketError::Format(err)
@@ -81,6 +324,12 @@ impl From<reqwest::Error> for PocketError {
     }
 }
 
+impl From<url::ParseError> for PocketError {
+    fn from(err: url::ParseError) -> PocketError {
+        PocketError::Url(err)
+    }
+}
+
 impl From<std::io::Error> for PocketError {
     fn from(err: std::io::Error) -> PocketError {
         PocketError::Io(err)
@@ -95,6 +344,19 @@ impl Error for PocketError {
             PocketError::Json(ref e) => e.description(),
             PocketError::Format(ref e) => e.description(),
             PocketError::Proto(..) => "protocol error",
+            PocketError::InvalidTag(..) => "invalid tag",
+            PocketError::InvalidSearch(..) => "invalid search expression",
+            PocketError::NotAuthenticated => "no access token, authorize first",
+            PocketError::NoRequestToken => "no request token, get auth url first",
+            PocketError::StateMismatch => "OAuth state mismatch",
+            PocketError::MalformedHeader(..) => "malformed response header",
+            PocketError::MissingEnvVar(..) => "missing environment variable",
+            PocketError::UnknownAccount(..) => "no such account in the credential store",
+            PocketError::InsecureFile(..) => "file is accessible by other users",
+            PocketError::WrongPassphrase => "wrong passphrase or corrupted vault",
+            PocketError::Vault(..) => "vault error",
+            PocketError::RateLimited { .. } => "rate limit exhausted",
+            PocketError::Url(ref e) => e.description(),
         }
     }
 
@@ -105,6 +367,19 @@ impl Error for PocketError {
             PocketError::Json(ref e) => Some(e),
             PocketError::Format(ref e) => Some(e),
             PocketError::Proto(..) => None,
+            PocketError::InvalidTag(..) => None,
+            PocketError::InvalidSearch(..) => None,
+            PocketError::NotAuthenticated => None,
+            PocketError::NoRequestToken => None,
+            PocketError::StateMismatch => None,
+            PocketError::MalformedHeader(..) => None,
+            PocketError::MissingEnvVar(..) => None,
+            PocketError::UnknownAccount(..) => None,
+            PocketError::InsecureFile(..) => None,
+            PocketError::WrongPassphrase => None,
+            PocketError::Vault(..) => None,
+            PocketError::RateLimited { .. } => None,
+            PocketError::Url(ref e) => Some(e),
         }
     }
 }
@@ -116,20 +391,225 @@ impl std::fmt::Display for PocketError {
             PocketError::Io(ref e) => e.fmt(fmt),
             PocketError::Json(ref e) => e.fmt(fmt),
             PocketError::Format(ref e) => e.fmt(fmt),
-            PocketError::Proto(ref code, ref msg) => {
-                fmt.write_str(&*format!("{} (code {})", msg, code))
+            PocketError::Proto(ref err, ref msg) => fmt.write_str(&*format!("{}: {}", err, msg)),
+            PocketError::InvalidTag(ref tag) => fmt.write_str(&*format!("invalid tag `{}`", tag)),
+            PocketError::InvalidSearch(ref msg) => fmt.write_str(msg),
+            PocketError::NotAuthenticated
+            | PocketError::NoRequestToken
+            | PocketError::StateMismatch
+            | PocketError::WrongPassphrase => fmt.write_str(self.description()),
+            PocketError::MalformedHeader(ref name) => {
+                fmt.write_str(&*format!("malformed `{}` header", name))
+            }
+            PocketError::MissingEnvVar(ref name) => {
+                fmt.write_str(&*format!("environment variable `{}` is not set", name))
+            }
+            PocketError::UnknownAccount(ref name) => {
+                fmt.write_str(&*format!("no account `{}` in the credential store", name))
+            }
+            PocketError::InsecureFile(ref path) => fmt.write_str(&*format!(
+                "`{}` is accessible by other users, run `chmod 600` on it",
+                path.display()
+            )),
+            PocketError::Vault(ref msg) => fmt.write_str(msg),
+            PocketError::RateLimited {
+                reset_in: Some(reset_in),
+            } => fmt.write_str(&*format!(
+                "rate limit exhausted, reset in {}s",
+                reset_in.as_secs()
+            )),
+            PocketError::RateLimited { reset_in: None } => fmt.write_str(self.description()),
+            PocketError::Url(ref e) => e.fmt(fmt),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct Tag(String);
+
+impl Tag {
+    pub fn new(tag: &str) -> PocketResult<Tag> {
+        let tag = tag.trim();
+        if tag.is_empty() || tag.contains(',') {
+            Err(PocketError::InvalidTag(tag.to_string()))
+        } else {
+            Ok(Tag(tag.to_string()))
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &*self.0
+    }
+}
+
+impl FromStr for Tag {
+    type Err = PocketError;
+
+    fn from_str(s: &str) -> PocketResult<Tag> {
+        Tag::new(s)
+    }
+}
+
+impl AsRef<str> for Tag {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+impl Encodable for Tag {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        e.emit_str(self.as_str())
+    }
+}
+
+impl Decodable for Tag {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Tag, D::Error> {
+        let tag = d.read_str()?;
+        Tag::new(&*tag).map_err(|e| d.error(&*e.to_string()))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TagSet(BTreeSet<Tag>);
+
+impl TagSet {
+    pub fn new() -> TagSet {
+        TagSet(BTreeSet::new())
+    }
+
+    pub fn insert(&mut self, tag: Tag) -> bool {
+        self.0.insert(tag)
+    }
+
+    pub fn remove(&mut self, tag: &Tag) -> bool {
+        self.0.remove(tag)
+    }
+
+    pub fn contains(&self, tag: &Tag) -> bool {
+        self.0.contains(tag)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> btree_set::Iter<Tag> {
+        self.0.iter()
+    }
+}
+
+impl From<Tag> for TagSet {
+    fn from(tag: Tag) -> TagSet {
+        let mut tags = TagSet::new();
+        tags.insert(tag);
+        tags
+    }
+}
+
+impl FromIterator<Tag> for TagSet {
+    fn from_iter<I: IntoIterator<Item = Tag>>(iter: I) -> TagSet {
+        TagSet(iter.into_iter().collect())
+    }
+}
+
+impl Extend<Tag> for TagSet {
+    fn extend<I: IntoIterator<Item = Tag>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl<'a> IntoIterator for &'a TagSet {
+    type Item = &'a Tag;
+    type IntoIter = btree_set::Iter<'a, Tag>;
+
+    fn into_iter(self) -> btree_set::Iter<'a, Tag> {
+        self.iter()
+    }
+}
+
+impl FromStr for TagSet {
+    type Err = PocketError;
+
+    fn from_str(s: &str) -> PocketResult<TagSet> {
+        s.split(',')
+            .filter(|t| !t.trim().is_empty())
+            .map(Tag::new)
+            .collect()
+    }
+}
+
+impl fmt::Display for TagSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, tag) in self.iter().enumerate() {
+            if i > 0 {
+                fmt.write_str(",")?;
             }
+            fmt.write_str(tag.as_str())?;
         }
+        Ok(())
+    }
+}
+
+impl Encodable for TagSet {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        e.emit_str(&*self.to_string())
+    }
+}
+
+impl Decodable for TagSet {
+    fn decode<D: Decoder>(d: &mut D) -> Result<TagSet, D::Error> {
+        let tags = d.read_str()?;
+        tags.parse()
+            .map_err(|e: PocketError| d.error(&*e.to_string()))
     }
 }
 
 pub struct Pocket {
     consumer_key: String,
-    access_token: Option<String>,
-    code: Option<String>,
+    access_token: String,
+    base_url: String,
+    strict: bool,
+    throttle: Throttle,
+    rate_limits: Option<RateLimits>,
+    retry: RetryPolicy,
     client: Client,
 }
 
+const DEFAULT_BASE_URL: &str = "https://getpocket.com";
+
+thread_local!(static STRICT_DECODING: Cell<bool> = Cell::new(false));
+
+// Decoders have no access to the client, so the mode is passed through a thread local.
+fn decode_with_mode<T, F: FnOnce() -> T>(strict: bool, f: F) -> T {
+    let prev = STRICT_DECODING.with(|s| s.replace(strict));
+    let result = f();
+    STRICT_DECODING.with(|s| s.set(prev));
+    result
+}
+
+fn unknown_value<D: Decoder, T: fmt::Display>(
+    d: &mut D,
+    what: &str,
+    value: T,
+) -> Result<T, D::Error> {
+    if STRICT_DECODING.with(|s| s.get()) {
+        Err(d.error(&*format!("unknown {} {}", what, value)))
+    } else {
+        Ok(value)
+    }
+}
+
 #[derive(RustcEncodable)]
 pub struct PocketOAuthRequest<'a> {
     consumer_key: &'a str,
@@ -153,6 +633,7 @@ pub struct PocketAuthorizeRequest<'a> {
 pub struct PocketAuthorizeResponse {
     access_token: String,
     username: String,
+    state: Option<String>,
 }
 
 #[derive(RustcEncodable)]
@@ -161,10 +642,76 @@ pub struct PocketAddRequest<'a> {
     access_token: &'a str,
     url: &'a str,
     title: Option<&'a str>,
-    tags: Option<&'a str>,
+    tags: Option<&'a TagSet>,
     tweet_id: Option<&'a str>,
 }
 
+// Pocket sends related objects as a map keyed by id, or as an empty array
+// if there are none. As a decoder can't look ahead, such arrays are replaced
+// with empty maps by `parse_json` before decoding. A missing list is empty.
+fn read_keyed_list<D: Decoder, T: Decodable>(d: &mut D) -> Result<Vec<T>, D::Error> {
+    d.read_option(|d, present| {
+        if !present {
+            return Ok(Vec::new());
+        }
+        d.read_map(|d, s| {
+            (0..s)
+                .map(|i| {
+                    d.read_map_elt_key(i, |d| d.read_str())?;
+                    d.read_map_elt_val(i, Decodable::decode)
+                })
+                .collect()
+        })
+    })
+}
+
+const KEYED_LISTS: [&str; 5] = ["list", "tags", "authors", "images", "videos"];
+
+fn empty_lists_to_maps(value: &mut Json) {
+    match *value {
+        Json::Object(ref mut object) => {
+            for (key, value) in object.iter_mut() {
+                match *value {
+                    Json::Array(ref array) if array.is_empty() && KEYED_LISTS.contains(&&**key) => {
+                        *value = Json::Object(json::Object::new())
+                    }
+                    _ => empty_lists_to_maps(value),
+                }
+            }
+        }
+        Json::Array(ref mut array) => {
+            for value in array.iter_mut() {
+                empty_lists_to_maps(value);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn parse_json(data: &str) -> json::DecodeResult<Json> {
+    let mut value = Json::from_str(data).map_err(json::DecoderError::ParseError)?;
+    empty_lists_to_maps(&mut value);
+    Ok(value)
+}
+
+fn decode_json<T: Decodable>(data: &str) -> json::DecodeResult<T> {
+    Decodable::decode(&mut json::Decoder::new(parse_json(data)?))
+}
+
+#[derive(RustcDecodable, Debug, PartialEq, Clone)]
+pub struct ItemTag {
+    pub item_id: u64, // String
+    pub tag: Tag,
+}
+
+#[derive(RustcDecodable, Debug, PartialEq, Clone)]
+pub struct ItemAuthor {
+    pub item_id: u64,   // String
+    pub author_id: u64, // String
+    pub name: String,
+    pub url: String,
+}
+
 #[derive(RustcDecodable, Debug, PartialEq)]
 pub struct ItemImage {
     pub item_id: u64,  // String
@@ -185,7 +732,7 @@ pub struct ItemVideo {
     pub height: u16,           // String
     pub length: Option<usize>, // String
     pub vid: String,
-    pub vtype: u16,
+    pub vtype: ItemVideoType,
 }
 
 impl Decodable for ItemVideo {
@@ -207,27 +754,57 @@ impl Decodable for ItemVideo {
                     })
                 })?,
                 vid: d.read_struct_field("vid", 6, |d| d.read_str())?,
-                vtype: d.read_struct_field("type", 7, |d| d.read_u16())?,
+                vtype: d.read_struct_field("type", 7, Decodable::decode)?,
             })
         })
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ItemVideoType {
+    YouTube,
+    VimeoMoogaloop,
+    VimeoIframe,
+    Html5,
+    Flash,
+    Iframe,
+    Brightcove,
+    Vine,
+    Unknown(u16),
+}
+
+impl Decodable for ItemVideoType {
+    fn decode<D: Decoder>(d: &mut D) -> Result<ItemVideoType, D::Error> {
+        match d.read_u16()? {
+            1 => Ok(ItemVideoType::YouTube),
+            2 => Ok(ItemVideoType::VimeoMoogaloop),
+            3 => Ok(ItemVideoType::VimeoIframe),
+            4 => Ok(ItemVideoType::Html5),
+            5 => Ok(ItemVideoType::Flash),
+            6 => Ok(ItemVideoType::Iframe),
+            7 => Ok(ItemVideoType::Brightcove),
+            8 => Ok(ItemVideoType::Vine),
+            v => unknown_value(d, "video type", v).map(ItemVideoType::Unknown),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PocketItemHas {
-    No = 0,
-    Yes = 1,
-    Is = 2,
+    No,
+    Yes,
+    Is,
+    Unknown(u8),
 }
 
 impl Decodable for PocketItemHas {
     fn decode<D: Decoder>(d: &mut D) -> Result<PocketItemHas, D::Error> {
-        d.read_u8().map(|v| match v {
-            0 => PocketItemHas::No,
-            1 => PocketItemHas::Yes,
-            2 => PocketItemHas::Is,
-            _ => unreachable!(),
-        })
+        match d.read_u8()? {
+            0 => Ok(PocketItemHas::No),
+            1 => Ok(PocketItemHas::Yes),
+            2 => Ok(PocketItemHas::Is),
+            v => unknown_value(d, "has_image/has_video value", v).map(PocketItemHas::Unknown),
+        }
     }
 }
 
@@ -266,15 +843,15 @@ pub struct PocketAddedItem {
     pub has_image: PocketItemHas, // String
     pub has_video: PocketItemHas, // String
 
-    //pub tags: Vec<ItemTag>, // ???
-    //pub authors: Vec<ItemAuthor>, // ???
-    pub videos: Vec<ItemVideo>, // encoded as object with integer indices
-    pub images: Vec<ItemImage>, // if present, as empty array otherwise
+    pub tags: Vec<ItemTag>,       // encoded as object with tag keys
+    pub authors: Vec<ItemAuthor>, // encoded as object with author id keys
+    pub videos: Vec<ItemVideo>,   // encoded as object with integer indices
+    pub images: Vec<ItemImage>,   // if present, as empty array otherwise
 }
 
 impl Decodable for PocketAddedItem {
     fn decode<D: Decoder>(d: &mut D) -> Result<PocketAddedItem, D::Error> {
-        d.read_struct("PocketAddedItem", 28, |d| {
+        d.read_struct("PocketAddedItem", 30, |d| {
             Ok(PocketAddedItem {
                 item_id: d.read_struct_field("item_id", 0, |d| d.read_u64())?,
                 extended_item_id: d.read_struct_field("extended_item_id", 1, |d| d.read_u64())?,
@@ -318,22 +895,10 @@ impl Decodable for PocketAddedItem {
                 has_image: d.read_struct_field("has_image", 24, Decodable::decode)?,
                 has_video: d.read_struct_field("has_video", 25, Decodable::decode)?,
 
-                videos: d.read_struct_field("videos", 26, |d| {
-                    d.read_seq(|d, s| {
-                        Ok((0..s)
-                            .flat_map(|i| d.read_seq_elt(i, Decodable::decode))
-                            .into_iter()
-                            .collect())
-                    })
-                })?,
-                images: d.read_struct_field("images", 27, |d| {
-                    d.read_seq(|d, s| {
-                        Ok((0..s)
-                            .flat_map(|i| d.read_seq_elt(i, Decodable::decode))
-                            .into_iter()
-                            .collect())
-                    })
-                })?,
+                videos: d.read_struct_field("videos", 26, read_keyed_list)?,
+                images: d.read_struct_field("images", 27, read_keyed_list)?,
+                tags: d.read_struct_field("tags", 28, read_keyed_list)?,
+                authors: d.read_struct_field("authors", 29, read_keyed_list)?,
             })
         })
     }
@@ -345,193 +910,323 @@ pub struct PocketAddResponse {
     status: u16,
 }
 
-pub struct PocketGetRequest<'a> {
-    pocket: &'a mut Pocket,
+macro_rules! impl_query_builder {
+    ($ty:ty) => {
+        pub fn search(&mut self, search: &str) -> &mut $ty {
+            self.query_mut().search = Some(search.to_string());
+            self
+        }
 
-    search: Option<&'a str>,
-    domain: Option<&'a str>,
+        pub fn domain(&mut self, domain: &str) -> &mut $ty {
+            self.query_mut().domain = Some(domain.to_string());
+            self
+        }
 
-    tag: Option<PocketGetTag<'a>>,
-    state: Option<PocketGetState>,
-    content_type: Option<PocketGetType>,
-    detail_type: Option<PocketGetDetail>,
-    favorite: Option<bool>,
-    since: Option<Timespec>,
+        pub fn tag(&mut self, tag: PocketGetTag) -> &mut $ty {
+            self.query_mut().tag = Some(tag);
+            self
+        }
 
-    sort: Option<PocketGetSort>,
-    count: Option<usize>,
-    offset: Option<usize>,
-}
+        pub fn state(&mut self, state: PocketGetState) -> &mut $ty {
+            self.query_mut().state = Some(state);
+            self
+        }
 
-impl<'a> Encodable for PocketGetRequest<'a> {
-    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
-        e.emit_struct("PocketGetRequest", 13, |e| {
-            e.emit_struct_field("consumer_key", 0, |e| self.pocket.consumer_key.encode(e))
-                .and_then(|_| {
-                    e.emit_struct_field("access_token", 1, |e| {
-                        self.pocket.access_token.as_ref().unwrap().encode(e)
-                    })
-                })
-                .and_then(|_| e.emit_struct_field("search", 2, |e| self.search.encode(e)))
-                .and_then(|_| e.emit_struct_field("domain", 3, |e| self.domain.encode(e)))
-                .and_then(|_| e.emit_struct_field("tag", 4, |e| self.tag.encode(e)))
-                .and_then(|_| e.emit_struct_field("state", 5, |e| self.state.encode(e)))
-                .and_then(|_| {
-                    e.emit_struct_field("content_type", 6, |e| self.content_type.encode(e))
-                })
-                .and_then(|_| e.emit_struct_field("detail_type", 7, |e| self.detail_type.encode(e)))
-                .and_then(|_| e.emit_struct_field("favorite", 8, |e| self.favorite.encode(e)))
-                .and_then(|_| {
-                    e.emit_struct_field("since", 9, |e| self.since.map(|v| v.sec).encode(e))
-                })
-                .and_then(|_| e.emit_struct_field("sort", 10, |e| self.sort.encode(e)))
-                .and_then(|_| e.emit_struct_field("count", 11, |e| self.count.encode(e)))
-                .and_then(|_| e.emit_struct_field("offset", 12, |e| self.offset.encode(e)))
-        })
-    }
-}
+        pub fn content_type(&mut self, content_type: PocketGetType) -> &mut $ty {
+            self.query_mut().content_type = Some(content_type);
+            self
+        }
 
-impl<'a> PocketGetRequest<'a> {
-    fn new(pocket: &'a mut Pocket) -> PocketGetRequest<'a> {
-        PocketGetRequest {
-            pocket: pocket,
-            search: None,
-            domain: None,
-            tag: None,
-            state: None,
-            content_type: None,
-            detail_type: None,
-            favorite: None,
-            since: None,
-            sort: None,
-            count: None,
-            offset: None,
-        }
-    }
-
-    pub fn search<'b>(&'b mut self, search: &'a str) -> &'b mut PocketGetRequest<'a> {
-        self.search = Some(search);
-        self
-    }
+        pub fn detail_type(&mut self, detail_type: PocketGetDetail) -> &mut $ty {
+            self.query_mut().detail_type = Some(detail_type);
+            self
+        }
 
-    pub fn domain<'b>(&'b mut self, domain: &'a str) -> &'b mut PocketGetRequest<'a> {
-        self.domain = Some(domain);
-        self
-    }
+        pub fn complete(&mut self) -> &mut $ty {
+            self.detail_type(PocketGetDetail::Complete)
+        }
 
-    pub fn tag<'b>(&'b mut self, tag: PocketGetTag<'a>) -> &'b mut PocketGetRequest<'a> {
-        self.tag = Some(tag);
-        self
-    }
+        pub fn simple(&mut self) -> &mut $ty {
+            self.detail_type(PocketGetDetail::Simple)
+        }
 
-    pub fn state<'b>(&'b mut self, state: PocketGetState) -> &'b mut PocketGetRequest<'a> {
-        self.state = Some(state);
-        self
-    }
+        pub fn archived(&mut self) -> &mut $ty {
+            self.state(PocketGetState::Archive)
+        }
 
-    pub fn content_type<'b>(
-        &'b mut self,
-        content_type: PocketGetType,
-    ) -> &'b mut PocketGetRequest<'a> {
-        self.content_type = Some(content_type);
-        self
-    }
+        pub fn unread(&mut self) -> &mut $ty {
+            self.state(PocketGetState::Unread)
+        }
 
-    pub fn detail_type<'b>(
-        &'b mut self,
-        detail_type: PocketGetDetail,
-    ) -> &'b mut PocketGetRequest<'a> {
-        self.detail_type = Some(detail_type);
-        self
-    }
+        pub fn articles(&mut self) -> &mut $ty {
+            self.content_type(PocketGetType::Article)
+        }
 
-    pub fn complete<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.detail_type(PocketGetDetail::Complete)
-    }
+        pub fn videos(&mut self) -> &mut $ty {
+            self.content_type(PocketGetType::Video)
+        }
 
-    pub fn simple<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.detail_type(PocketGetDetail::Simple)
-    }
+        pub fn images(&mut self) -> &mut $ty {
+            self.content_type(PocketGetType::Image)
+        }
 
-    pub fn archived<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.state(PocketGetState::Archive)
-    }
+        pub fn favorite(&mut self, fav: bool) -> &mut $ty {
+            self.query_mut().favorite = Some(fav);
+            self
+        }
 
-    pub fn unread<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.state(PocketGetState::Unread)
-    }
+        pub fn since(&mut self, since: Timespec) -> &mut $ty {
+            self.query_mut().since = Some(since);
+            self
+        }
 
-    pub fn articles<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.content_type(PocketGetType::Article)
-    }
+        pub fn sort(&mut self, sort: PocketGetSort) -> &mut $ty {
+            self.query_mut().sort = Some(sort);
+            self
+        }
 
-    pub fn videos<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.content_type(PocketGetType::Video)
-    }
+        pub fn sort_by_newest(&mut self) -> &mut $ty {
+            self.sort(PocketGetSort::Newest)
+        }
 
-    pub fn images<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.content_type(PocketGetType::Image)
-    }
+        pub fn sort_by_oldest(&mut self) -> &mut $ty {
+            self.sort(PocketGetSort::Oldest)
+        }
 
-    pub fn favorite<'b>(&'b mut self, fav: bool) -> &'b mut PocketGetRequest<'a> {
-        self.favorite = Some(fav);
-        self
-    }
+        pub fn sort_by_title(&mut self) -> &mut $ty {
+            self.sort(PocketGetSort::Title)
+        }
 
-    pub fn since<'b>(&'b mut self, since: Timespec) -> &'b mut PocketGetRequest<'a> {
-        self.since = Some(since);
-        self
+        pub fn sort_by_site(&mut self) -> &mut $ty {
+            self.sort(PocketGetSort::Site)
+        }
+
+        pub fn offset(&mut self, offset: usize) -> &mut $ty {
+            self.query_mut().offset = Some(offset);
+            self
+        }
+
+        pub fn count(&mut self, count: usize) -> &mut $ty {
+            self.query_mut().count = Some(count);
+            self
+        }
+
+        pub fn slice(&mut self, offset: usize, count: usize) -> &mut $ty {
+            self.offset(offset).count(count)
+        }
+    };
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Query {
+    search: Option<String>,
+    domain: Option<String>,
+
+    tag: Option<PocketGetTag>,
+    state: Option<PocketGetState>,
+    content_type: Option<PocketGetType>,
+    detail_type: Option<PocketGetDetail>,
+    favorite: Option<bool>,
+    since: Option<Timespec>,
+
+    sort: Option<PocketGetSort>,
+    count: Option<usize>,
+    offset: Option<usize>,
+}
+
+impl Query {
+    pub fn new() -> Query {
+        Query::default()
     }
 
-    pub fn sort<'b>(&'b mut self, sort: PocketGetSort) -> &'b mut PocketGetRequest<'a> {
-        self.sort = Some(sort);
+    #[inline]
+    fn query_mut(&mut self) -> &mut Query {
         self
     }
 
-    pub fn sort_by_newest<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.sort(PocketGetSort::Newest)
+    impl_query_builder!(Query);
+
+    fn encode_fields<S: Encoder>(&self, e: &mut S, first: usize) -> Result<(), S::Error> {
+        e.emit_struct_field("search", first, |e| self.search.encode(e))?;
+        e.emit_struct_field("domain", first + 1, |e| self.domain.encode(e))?;
+        e.emit_struct_field("tag", first + 2, |e| self.tag.encode(e))?;
+        e.emit_struct_field("state", first + 3, |e| self.state.encode(e))?;
+        e.emit_struct_field("content_type", first + 4, |e| self.content_type.encode(e))?;
+        e.emit_struct_field("detail_type", first + 5, |e| self.detail_type.encode(e))?;
+        e.emit_struct_field("favorite", first + 6, |e| self.favorite.encode(e))?;
+        e.emit_struct_field("since", first + 7, |e| self.since.map(|v| v.sec).encode(e))?;
+        e.emit_struct_field("sort", first + 8, |e| self.sort.encode(e))?;
+        e.emit_struct_field("count", first + 9, |e| self.count.encode(e))?;
+        e.emit_struct_field("offset", first + 10, |e| self.offset.encode(e))
     }
+}
 
-    pub fn sort_by_oldest<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.sort(PocketGetSort::Oldest)
+impl Encodable for Query {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        e.emit_struct("Query", 11, |e| self.encode_fields(e, 0))
     }
+}
 
-    pub fn sort_by_title<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.sort(PocketGetSort::Title)
+impl Decodable for Query {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Query, D::Error> {
+        d.read_struct("Query", 11, |d| {
+            Ok(Query {
+                search: d.read_struct_field("search", 0, Decodable::decode)?,
+                domain: d.read_struct_field("domain", 1, Decodable::decode)?,
+                tag: d.read_struct_field("tag", 2, Decodable::decode)?,
+                state: d.read_struct_field("state", 3, Decodable::decode)?,
+                content_type: d.read_struct_field("content_type", 4, Decodable::decode)?,
+                detail_type: d.read_struct_field("detail_type", 5, Decodable::decode)?,
+                favorite: d.read_struct_field("favorite", 6, Decodable::decode)?,
+                since: d
+                    .read_struct_field("since", 7, |d| Option::<i64>::decode(d))?
+                    .map(|v| Timespec::new(v, 0)),
+                sort: d.read_struct_field("sort", 8, Decodable::decode)?,
+                count: d.read_struct_field("count", 9, Decodable::decode)?,
+                offset: d.read_struct_field("offset", 10, Decodable::decode)?,
+            })
+        })
     }
+}
 
-    pub fn sort_by_site<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
-        self.sort(PocketGetSort::Site)
+pub struct PocketGetRequest<'a> {
+    pocket: &'a mut Pocket,
+    query: Query,
+}
+
+impl<'a> Encodable for PocketGetRequest<'a> {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        e.emit_struct("PocketGetRequest", 13, |e| {
+            e.emit_struct_field("consumer_key", 0, |e| self.pocket.consumer_key.encode(e))
+                .and_then(|_| {
+                    e.emit_struct_field("access_token", 1, |e| self.pocket.access_token.encode(e))
+                })
+                .and_then(|_| self.query.encode_fields(e, 2))
+        })
     }
+}
 
-    pub fn offset<'b>(&'b mut self, offset: usize) -> &'b mut PocketGetRequest<'a> {
-        self.offset = Some(offset);
-        self
+impl<'a> PocketGetRequest<'a> {
+    fn new(pocket: &'a mut Pocket, query: Query) -> PocketGetRequest<'a> {
+        PocketGetRequest {
+            pocket: pocket,
+            query: query,
+        }
     }
 
-    pub fn count<'b>(&'b mut self, count: usize) -> &'b mut PocketGetRequest<'a> {
-        self.count = Some(count);
-        self
+    #[inline]
+    pub fn query(&self) -> &Query {
+        &self.query
     }
 
-    pub fn slice<'b>(&'b mut self, offset: usize, count: usize) -> &'b mut PocketGetRequest<'a> {
-        self.offset(offset).count(count)
+    #[inline]
+    fn query_mut(&mut self) -> &mut Query {
+        &mut self.query
     }
 
+    impl_query_builder!(PocketGetRequest<'a>);
+
     pub fn get(self) -> PocketResult<Vec<PocketItem>> {
+        self.get_with_meta().map(|v| v.list)
+    }
+
+    pub fn get_with_meta(mut self) -> PocketResult<PocketGetResponse> {
+        self.fetch()
+    }
+
+    pub fn pages(self, page_size: usize) -> PocketGetPages<'a> {
+        PocketGetPages {
+            request: self,
+            page_size: cmp::max(page_size, 1),
+            done: false,
+        }
+    }
+
+    pub fn iter(self) -> PocketGetIter<'a> {
+        let page_size = self.query.count.unwrap_or(DEFAULT_PAGE_SIZE);
+        PocketGetIter {
+            pages: self.pages(page_size),
+            page: Vec::new().into_iter(),
+        }
+    }
+
+    fn fetch(&mut self) -> PocketResult<PocketGetResponse> {
+        let request = self.to_json()?;
+        self.pocket.request("get", &*request, true)
+    }
+
+    fn to_json(&self) -> PocketResult<String> {
         let mut request = String::new();
         {
             let mut encoder = json::Encoder::new(&mut request);
-            self.encode(&mut encoder).unwrap();
+            self.encode(&mut encoder)?;
         }
+        Ok(request)
+    }
+}
+
+const DEFAULT_PAGE_SIZE: usize = 30;
+
+pub struct PocketGetPages<'a> {
+    request: PocketGetRequest<'a>,
+    page_size: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for PocketGetPages<'a> {
+    type Item = PocketResult<Vec<PocketItem>>;
 
-        self.pocket
-            .request("https://getpocket.com/v3/get", &*request)
-            .map(|v: PocketGetResponse| v.list)
+    fn next(&mut self) -> Option<PocketResult<Vec<PocketItem>>> {
+        if self.done {
+            return None;
+        }
+
+        let offset = self.request.query.offset.unwrap_or(0);
+        self.request.slice(offset, self.page_size);
+        match self.request.fetch() {
+            Ok(response) => {
+                // items are never dropped while decoding, so this is the count sent by the server
+                let mut items = response.list;
+                self.done = items.len() < self.page_size;
+                self.request.offset(offset + self.page_size);
+                // the list is keyed by item id, so restore the requested order
+                items.sort_by_key(|item| item.sort_id);
+                if items.is_empty() {
+                    None
+                } else {
+                    Some(Ok(items))
+                }
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+pub struct PocketGetIter<'a> {
+    pages: PocketGetPages<'a>,
+    page: vec::IntoIter<PocketItem>,
+}
+
+impl<'a> Iterator for PocketGetIter<'a> {
+    type Item = PocketResult<PocketItem>;
+
+    fn next(&mut self) -> Option<PocketResult<PocketItem>> {
+        loop {
+            if let Some(item) = self.page.next() {
+                return Some(Ok(item));
+            }
+            match self.pages.next()? {
+                Ok(page) => self.page = page.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PocketGetDetail {
     Simple,
     Complete,
@@ -546,7 +1241,18 @@ impl Encodable for PocketGetDetail {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl Decodable for PocketGetDetail {
+    fn decode<D: Decoder>(d: &mut D) -> Result<PocketGetDetail, D::Error> {
+        let value = d.read_str()?;
+        match &*value {
+            "simple" => Ok(PocketGetDetail::Simple),
+            "complete" => Ok(PocketGetDetail::Complete),
+            _ => Err(d.error(&*format!("unknown detail `{}`", value))),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PocketGetSort {
     Newest,
     Oldest,
@@ -565,7 +1271,20 @@ impl Encodable for PocketGetSort {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl Decodable for PocketGetSort {
+    fn decode<D: Decoder>(d: &mut D) -> Result<PocketGetSort, D::Error> {
+        let value = d.read_str()?;
+        match &*value {
+            "newest" => Ok(PocketGetSort::Newest),
+            "oldest" => Ok(PocketGetSort::Oldest),
+            "title" => Ok(PocketGetSort::Title),
+            "site" => Ok(PocketGetSort::Site),
+            _ => Err(d.error(&*format!("unknown sort `{}`", value))),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PocketGetState {
     Unread,
     Archive,
@@ -582,22 +1301,46 @@ impl Encodable for PocketGetState {
     }
 }
 
-#[derive(Debug)]
-pub enum PocketGetTag<'a> {
+impl Decodable for PocketGetState {
+    fn decode<D: Decoder>(d: &mut D) -> Result<PocketGetState, D::Error> {
+        let value = d.read_str()?;
+        match &*value {
+            "unread" => Ok(PocketGetState::Unread),
+            "archive" => Ok(PocketGetState::Archive),
+            "all" => Ok(PocketGetState::All),
+            _ => Err(d.error(&*format!("unknown state `{}`", value))),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PocketGetTag {
     Untagged,
-    Tagged(&'a str),
+    Tagged(Tag),
 }
 
-impl<'a> Encodable for PocketGetTag<'a> {
+impl Encodable for PocketGetTag {
     fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
         e.emit_str(match *self {
             PocketGetTag::Untagged => "_untagged_",
-            PocketGetTag::Tagged(ref s) => s,
+            PocketGetTag::Tagged(ref s) => s.as_str(),
         })
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl Decodable for PocketGetTag {
+    fn decode<D: Decoder>(d: &mut D) -> Result<PocketGetTag, D::Error> {
+        let value = d.read_str()?;
+        match &*value {
+            "_untagged_" => Ok(PocketGetTag::Untagged),
+            _ => Tag::new(&*value)
+                .map(PocketGetTag::Tagged)
+                .map_err(|e| d.error(&*e.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PocketGetType {
     Article,
     Video,
@@ -614,37 +1357,48 @@ impl Encodable for PocketGetType {
     }
 }
 
+impl Decodable for PocketGetType {
+    fn decode<D: Decoder>(d: &mut D) -> Result<PocketGetType, D::Error> {
+        let value = d.read_str()?;
+        match &*value {
+            "article" => Ok(PocketGetType::Article),
+            "video" => Ok(PocketGetType::Video),
+            "image" => Ok(PocketGetType::Image),
+            _ => Err(d.error(&*format!("unknown type `{}`", value))),
+        }
+    }
+}
+
+#[derive(RustcDecodable, Debug, PartialEq, Clone)]
+pub struct PocketSearchMeta {
+    pub search_type: Option<String>,
+    pub total_result_count: Option<usize>,
+    pub count: Option<usize>,
+    pub offset: Option<usize>,
+}
+
 #[derive(Debug)]
 pub struct PocketGetResponse {
-    list: Vec<PocketItem>, // must be Vec
-    status: u16,
-    complete: bool, // must be bool
-    error: Option<String>,
-    //search_meta: PocketSearchMeta,
-    since: Timespec,
+    pub list: Vec<PocketItem>, // must be Vec
+    pub status: u16,
+    pub complete: bool, // must be bool
+    pub error: Option<String>,
+    pub search_meta: Option<PocketSearchMeta>,
+    pub since: Timespec,
 }
 
 impl Decodable for PocketGetResponse {
     fn decode<D: Decoder>(d: &mut D) -> Result<PocketGetResponse, D::Error> {
-        d.read_struct("PocketGetResponse", 5, |d| {
+        d.read_struct("PocketGetResponse", 6, |d| {
             Ok(PocketGetResponse {
-                list: d.read_struct_field("list", 0, |d| {
-                    d.read_map(|d, s| {
-                        Ok((0..s)
-                            .flat_map(|i| {
-                                d.read_map_elt_key(i, |d| d.read_str())
-                                    .and_then(|_| d.read_map_elt_val(i, Decodable::decode))
-                                    .into_iter()
-                            })
-                            .collect())
-                    })
-                })?,
+                list: d.read_struct_field("list", 0, read_keyed_list)?,
                 status: d.read_struct_field("status", 1, |d| d.read_u16())?,
                 complete: d.read_struct_field("complete", 2, |d| d.read_u8().map(|v| v != 0))?,
                 error: d.read_struct_field("error", 3, |d| {
                     d.read_option(|d, b| if b { d.read_str().map(Some) } else { Ok(None) })
                 })?,
-                since: d.read_struct_field("since", 4, |d| {
+                search_meta: d.read_struct_field("search_meta", 4, Decodable::decode)?,
+                since: d.read_struct_field("since", 5, |d| {
                     d.read_u64().map(|v| Timespec::new(v as i64, 0))
                 })?,
             })
@@ -654,19 +1408,20 @@ impl Decodable for PocketGetResponse {
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PocketItemStatus {
-    Normal = 0,
-    Archived = 1,
-    Deleted = 2,
+    Normal,
+    Archived,
+    Deleted,
+    Unknown(u8),
 }
 
 impl Decodable for PocketItemStatus {
     fn decode<D: Decoder>(d: &mut D) -> Result<PocketItemStatus, D::Error> {
-        d.read_u8().map(|v| match v {
-            0 => PocketItemStatus::Normal,
-            1 => PocketItemStatus::Archived,
-            2 => PocketItemStatus::Deleted,
-            _ => unreachable!(),
-        })
+        match d.read_u8()? {
+            0 => Ok(PocketItemStatus::Normal),
+            1 => Ok(PocketItemStatus::Archived),
+            2 => Ok(PocketItemStatus::Deleted),
+            v => unknown_value(d, "item status", v).map(PocketItemStatus::Unknown),
+        }
     }
 }
 
@@ -702,11 +1457,19 @@ pub struct PocketItem {
     pub status: PocketItemStatus,
     pub images: Option<Vec<ItemImage>>,
     pub videos: Option<Vec<ItemVideo>>,
+    pub tags: Vec<ItemTag>,
+    pub authors: Vec<ItemAuthor>,
+}
+
+impl PocketItem {
+    pub fn tag_set(&self) -> TagSet {
+        self.tags.iter().map(|t| t.tag.clone()).collect()
+    }
 }
 
 impl Decodable for PocketItem {
     fn decode<D: Decoder>(d: &mut D) -> Result<PocketItem, D::Error> {
-        d.read_struct("PocketItem", 21, |d| {
+        d.read_struct("PocketItem", 23, |d| {
             Ok(PocketItem {
                 item_id: d.read_struct_field("item_id", 0, |d| d.read_u64())?,
 
@@ -746,14 +1509,7 @@ impl Decodable for PocketItem {
                 videos: d.read_struct_field("videos", 19, |d| {
                     d.read_option(|d, b| {
                         if b {
-                            d.read_map(|d, s| {
-                                Ok((0..s)
-                                    .flat_map(|i| {
-                                        d.read_map_elt_val(i, Decodable::decode).into_iter()
-                                    })
-                                    .collect())
-                            })
-                            .map(Some)
+                            read_keyed_list(d).map(Some)
                         } else {
                             Ok(None)
                         }
@@ -762,19 +1518,14 @@ impl Decodable for PocketItem {
                 images: d.read_struct_field("images", 20, |d| {
                     d.read_option(|d, b| {
                         if b {
-                            d.read_map(|d, s| {
-                                Ok((0..s)
-                                    .flat_map(|i| {
-                                        d.read_map_elt_val(i, Decodable::decode).into_iter()
-                                    })
-                                    .collect())
-                            })
-                            .map(Some)
+                            read_keyed_list(d).map(Some)
                         } else {
                             Ok(None)
                         }
                     })
                 })?,
+                tags: d.read_struct_field("tags", 21, read_keyed_list)?,
+                authors: d.read_struct_field("authors", 22, read_keyed_list)?,
             })
         })
     }
@@ -783,12 +1534,56 @@ impl Decodable for PocketItem {
 pub struct PocketAddAction<'a> {
     item_id: Option<u64>,
     ref_id: Option<&'a str>,
-    tags: Option<&'a str>,
+    tags: Option<&'a TagSet>,
     time: Option<u64>,
     title: Option<&'a str>,
     url: Option<&'a str>,
 }
 
+impl<'a> PocketAddAction<'a> {
+    pub fn new(url: &'a str) -> PocketAddAction<'a> {
+        PocketAddAction {
+            item_id: None,
+            ref_id: None,
+            tags: None,
+            time: None,
+            title: None,
+            url: Some(url),
+        }
+    }
+
+    pub fn readd(item_id: u64) -> PocketAddAction<'a> {
+        PocketAddAction {
+            item_id: Some(item_id),
+            ref_id: None,
+            tags: None,
+            time: None,
+            title: None,
+            url: None,
+        }
+    }
+
+    pub fn ref_id(mut self, ref_id: &'a str) -> PocketAddAction<'a> {
+        self.ref_id = Some(ref_id);
+        self
+    }
+
+    pub fn tags(mut self, tags: &'a TagSet) -> PocketAddAction<'a> {
+        self.tags = Some(tags);
+        self
+    }
+
+    pub fn title(mut self, title: &'a str) -> PocketAddAction<'a> {
+        self.title = Some(title);
+        self
+    }
+
+    pub fn at(mut self, time: u64) -> PocketAddAction<'a> {
+        self.time = Some(time);
+        self
+    }
+}
+
 impl<'a> PocketAction for PocketAddAction<'a> {
     fn name(&self) -> &'static str {
         "add"
@@ -797,99 +1592,355 @@ impl<'a> PocketAction for PocketAddAction<'a> {
 
 impl<'a> JsonEncodable for PocketAddAction<'a> {
     fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
-        e.emit_struct("PocketAddAction", 7, |e| {
-            e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))
-                .and_then(|_| e.emit_struct_field("item_id", 1, |e| self.item_id.encode(e)))
-                .and_then(|_| e.emit_struct_field("ref_id", 2, |e| self.ref_id.encode(e)))
-                .and_then(|_| e.emit_struct_field("tags", 3, |e| self.tags.encode(e)))
-                .and_then(|_| e.emit_struct_field("time", 4, |e| self.time.encode(e)))
-                .and_then(|_| e.emit_struct_field("title", 5, |e| self.title.encode(e)))
-                .and_then(|_| e.emit_struct_field("url", 6, |e| self.url.encode(e)))
-        })
+        Action::from(self).encode(e)
+    }
+}
+
+impl<'a, 'b> From<&'b PocketAddAction<'a>> for Action {
+    fn from(action: &'b PocketAddAction<'a>) -> Action {
+        Action::Add {
+            item_id: action.item_id,
+            ref_id: action.ref_id.map(|v| v.to_string()),
+            tags: action.tags.cloned(),
+            time: action.time,
+            title: action.title.map(|v| v.to_string()),
+            url: action.url.map(|v| v.to_string()),
+        }
     }
 }
 
-impl_item_pocket_action!("archive", PocketArchiveAction);
-impl_item_pocket_action!("readd", PocketReaddAction);
-impl_item_pocket_action!("favorite", PocketFavoriteAction);
-impl_item_pocket_action!("unfavorite", PocketUnfavoriteAction);
-impl_item_pocket_action!("delete", PocketDeleteAction);
+impl_item_pocket_action!("archive", PocketArchiveAction, Archive);
+impl_item_pocket_action!("readd", PocketReaddAction, Readd);
+impl_item_pocket_action!("favorite", PocketFavoriteAction, Favorite);
+impl_item_pocket_action!("unfavorite", PocketUnfavoriteAction, Unfavorite);
+impl_item_pocket_action!("delete", PocketDeleteAction, Delete);
+
+impl_item_tags_pocket_action!("tags_add", PocketTagsAddAction, TagsAdd);
+impl_item_tags_pocket_action!("tags_remove", PocketTagsRemoveAction, TagsRemove);
+impl_item_tags_pocket_action!("tags_replace", PocketTagsReplaceAction, TagsReplace);
+impl_item_pocket_action!("tags_clear", PocketTagsClearAction, TagsClear);
 
-pub struct PocketTagsAddAction<'a> {
-    item_id: u64,
-    tags: &'a str,
+pub struct PocketTagRenameAction<'a> {
+    old_tag: &'a Tag,
+    new_tag: &'a Tag,
     time: Option<u64>,
 }
 
-impl<'a> PocketAction for PocketTagsAddAction<'a> {
+impl<'a> PocketTagRenameAction<'a> {
+    pub fn new(old_tag: &'a Tag, new_tag: &'a Tag) -> PocketTagRenameAction<'a> {
+        PocketTagRenameAction {
+            old_tag: old_tag,
+            new_tag: new_tag,
+            time: None,
+        }
+    }
+
+    pub fn at(mut self, time: u64) -> PocketTagRenameAction<'a> {
+        self.time = Some(time);
+        self
+    }
+}
+
+impl<'a> PocketAction for PocketTagRenameAction<'a> {
     fn name(&self) -> &'static str {
-        "tags_add"
+        "tag_rename"
     }
 }
 
-impl<'a> JsonEncodable for PocketTagsAddAction<'a> {
+impl<'a> JsonEncodable for PocketTagRenameAction<'a> {
     fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
-        e.emit_struct("PocketTagsAddAction", 3, |e| {
-            e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))
-                .and_then(|_| e.emit_struct_field("tags", 1, |e| self.tags.encode(e)))
-                .and_then(|_| e.emit_struct_field("time", 2, |e| self.time.encode(e)))
-        })
+        Action::from(self).encode(e)
     }
 }
 
-pub struct PocketTagsReplaceAction<'a> {
-    item_id: u64,
-    tags: &'a str,
+impl<'a, 'b> From<&'b PocketTagRenameAction<'a>> for Action {
+    fn from(action: &'b PocketTagRenameAction<'a>) -> Action {
+        Action::TagRename {
+            old_tag: action.old_tag.clone(),
+            new_tag: action.new_tag.clone(),
+            time: action.time,
+        }
+    }
+}
+
+pub struct PocketTagDeleteAction<'a> {
+    tag: &'a Tag,
     time: Option<u64>,
 }
 
-impl<'a> PocketAction for PocketTagsReplaceAction<'a> {
+impl<'a> PocketTagDeleteAction<'a> {
+    pub fn new(tag: &'a Tag) -> PocketTagDeleteAction<'a> {
+        PocketTagDeleteAction {
+            tag: tag,
+            time: None,
+        }
+    }
+
+    pub fn at(mut self, time: u64) -> PocketTagDeleteAction<'a> {
+        self.time = Some(time);
+        self
+    }
+}
+
+impl<'a> PocketAction for PocketTagDeleteAction<'a> {
     fn name(&self) -> &'static str {
-        "tags_replace"
+        "tag_delete"
     }
 }
 
-impl<'a> JsonEncodable for PocketTagsReplaceAction<'a> {
+impl<'a> JsonEncodable for PocketTagDeleteAction<'a> {
     fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
-        e.emit_struct("PocketTagsReplaceAction", 4, |e| {
-            e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))
-                .and_then(|_| e.emit_struct_field("item_id", 1, |e| self.item_id.encode(e)))
-                .and_then(|_| e.emit_struct_field("tags", 2, |e| self.tags.encode(e)))
-                .and_then(|_| e.emit_struct_field("time", 3, |e| self.time.encode(e)))
-        })
+        Action::from(self).encode(e)
     }
 }
 
-impl_item_pocket_action!("tags_clear", PocketTagsClearAction);
+impl<'a, 'b> From<&'b PocketTagDeleteAction<'a>> for Action {
+    fn from(action: &'b PocketTagDeleteAction<'a>) -> Action {
+        Action::TagDelete {
+            tag: action.tag.clone(),
+            time: action.time,
+        }
+    }
+}
 
-pub struct PocketTagRenameAction<'a> {
-    item_id: u64,
-    old_tag: &'a str,
-    new_tag: &'a str,
-    time: Option<u64>,
+#[derive(Debug, PartialEq, Clone)]
+pub enum Action {
+    Add {
+        item_id: Option<u64>,
+        ref_id: Option<String>,
+        tags: Option<TagSet>,
+        time: Option<u64>,
+        title: Option<String>,
+        url: Option<String>,
+    },
+    Archive {
+        item_id: u64,
+        time: Option<u64>,
+    },
+    Readd {
+        item_id: u64,
+        time: Option<u64>,
+    },
+    Favorite {
+        item_id: u64,
+        time: Option<u64>,
+    },
+    Unfavorite {
+        item_id: u64,
+        time: Option<u64>,
+    },
+    Delete {
+        item_id: u64,
+        time: Option<u64>,
+    },
+    TagsAdd {
+        item_id: u64,
+        tags: TagSet,
+        time: Option<u64>,
+    },
+    TagsRemove {
+        item_id: u64,
+        tags: TagSet,
+        time: Option<u64>,
+    },
+    TagsReplace {
+        item_id: u64,
+        tags: TagSet,
+        time: Option<u64>,
+    },
+    TagsClear {
+        item_id: u64,
+        time: Option<u64>,
+    },
+    TagRename {
+        old_tag: Tag,
+        new_tag: Tag,
+        time: Option<u64>,
+    },
+    TagDelete {
+        tag: Tag,
+        time: Option<u64>,
+    },
 }
 
-impl<'a> PocketAction for PocketTagRenameAction<'a> {
+impl PocketAction for Action {
     fn name(&self) -> &'static str {
-        "tag_rename"
+        match *self {
+            Action::Add { .. } => "add",
+            Action::Archive { .. } => "archive",
+            Action::Readd { .. } => "readd",
+            Action::Favorite { .. } => "favorite",
+            Action::Unfavorite { .. } => "unfavorite",
+            Action::Delete { .. } => "delete",
+            Action::TagsAdd { .. } => "tags_add",
+            Action::TagsRemove { .. } => "tags_remove",
+            Action::TagsReplace { .. } => "tags_replace",
+            Action::TagsClear { .. } => "tags_clear",
+            Action::TagRename { .. } => "tag_rename",
+            Action::TagDelete { .. } => "tag_delete",
+        }
     }
 }
 
-impl<'a> JsonEncodable for PocketTagRenameAction<'a> {
-    fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
-        e.emit_struct("PocketTagRenameAction", 5, |e| {
-            e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))
-                .and_then(|_| e.emit_struct_field("item_id", 1, |e| self.item_id.encode(e)))
-                .and_then(|_| e.emit_struct_field("old_tag", 2, |e| self.old_tag.encode(e)))
-                .and_then(|_| e.emit_struct_field("new_tag", 3, |e| self.new_tag.encode(e)))
-                .and_then(|_| e.emit_struct_field("time", 4, |e| self.time.encode(e)))
+impl Encodable for Action {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        match *self {
+            Action::Add {
+                ref item_id,
+                ref ref_id,
+                ref tags,
+                ref time,
+                ref title,
+                ref url,
+            } => e.emit_struct("Action", 7, |e| {
+                e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))?;
+                e.emit_struct_field("item_id", 1, |e| item_id.encode(e))?;
+                e.emit_struct_field("ref_id", 2, |e| ref_id.encode(e))?;
+                e.emit_struct_field("tags", 3, |e| tags.encode(e))?;
+                e.emit_struct_field("time", 4, |e| time.encode(e))?;
+                e.emit_struct_field("title", 5, |e| title.encode(e))?;
+                e.emit_struct_field("url", 6, |e| url.encode(e))
+            }),
+            Action::Archive { item_id, ref time }
+            | Action::Readd { item_id, ref time }
+            | Action::Favorite { item_id, ref time }
+            | Action::Unfavorite { item_id, ref time }
+            | Action::Delete { item_id, ref time }
+            | Action::TagsClear { item_id, ref time } => e.emit_struct("Action", 3, |e| {
+                e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))?;
+                e.emit_struct_field("item_id", 1, |e| e.emit_u64(item_id))?;
+                e.emit_struct_field("time", 2, |e| time.encode(e))
+            }),
+            Action::TagsAdd {
+                item_id,
+                ref tags,
+                ref time,
+            }
+            | Action::TagsRemove {
+                item_id,
+                ref tags,
+                ref time,
+            }
+            | Action::TagsReplace {
+                item_id,
+                ref tags,
+                ref time,
+            } => e.emit_struct("Action", 4, |e| {
+                e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))?;
+                e.emit_struct_field("item_id", 1, |e| e.emit_u64(item_id))?;
+                e.emit_struct_field("tags", 2, |e| tags.encode(e))?;
+                e.emit_struct_field("time", 3, |e| time.encode(e))
+            }),
+            Action::TagRename {
+                ref old_tag,
+                ref new_tag,
+                ref time,
+            } => e.emit_struct("Action", 4, |e| {
+                e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))?;
+                e.emit_struct_field("old_tag", 1, |e| old_tag.encode(e))?;
+                e.emit_struct_field("new_tag", 2, |e| new_tag.encode(e))?;
+                e.emit_struct_field("time", 3, |e| time.encode(e))
+            }),
+            Action::TagDelete { ref tag, ref time } => e.emit_struct("Action", 3, |e| {
+                e.emit_struct_field("name", 0, |e| e.emit_str(self.name()))?;
+                e.emit_struct_field("tag", 1, |e| tag.encode(e))?;
+                e.emit_struct_field("time", 2, |e| time.encode(e))
+            }),
+        }
+    }
+}
+
+impl Decodable for Action {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Action, D::Error> {
+        d.read_struct("Action", 10, |d| {
+            let name: String = d.read_struct_field("name", 0, |d| d.read_str())?;
+            let item_id: Option<u64> = d.read_struct_field("item_id", 1, Decodable::decode)?;
+            let ref_id: Option<String> = d.read_struct_field("ref_id", 2, Decodable::decode)?;
+            let tags: Option<TagSet> = d.read_struct_field("tags", 3, Decodable::decode)?;
+            let time: Option<u64> = d.read_struct_field("time", 4, Decodable::decode)?;
+            let title: Option<String> = d.read_struct_field("title", 5, Decodable::decode)?;
+            let url: Option<String> = d.read_struct_field("url", 6, Decodable::decode)?;
+            let old_tag: Option<Tag> = d.read_struct_field("old_tag", 7, Decodable::decode)?;
+            let new_tag: Option<Tag> = d.read_struct_field("new_tag", 8, Decodable::decode)?;
+            let tag: Option<Tag> = d.read_struct_field("tag", 9, Decodable::decode)?;
+
+            macro_rules! required {
+                ($field:ident) => {
+                    match $field {
+                        Some(v) => v,
+                        None => {
+                            return Err(d.error(concat!("missing field `", stringify!($field), "`")))
+                        }
+                    }
+                };
+            }
+
+            match &*name {
+                "add" => Ok(Action::Add {
+                    item_id: item_id,
+                    ref_id: ref_id,
+                    tags: tags,
+                    time: time,
+                    title: title,
+                    url: url,
+                }),
+                "archive" => Ok(Action::Archive {
+                    item_id: required!(item_id),
+                    time: time,
+                }),
+                "readd" => Ok(Action::Readd {
+                    item_id: required!(item_id),
+                    time: time,
+                }),
+                "favorite" => Ok(Action::Favorite {
+                    item_id: required!(item_id),
+                    time: time,
+                }),
+                "unfavorite" => Ok(Action::Unfavorite {
+                    item_id: required!(item_id),
+                    time: time,
+                }),
+                "delete" => Ok(Action::Delete {
+                    item_id: required!(item_id),
+                    time: time,
+                }),
+                "tags_add" => Ok(Action::TagsAdd {
+                    item_id: required!(item_id),
+                    tags: required!(tags),
+                    time: time,
+                }),
+                "tags_remove" => Ok(Action::TagsRemove {
+                    item_id: required!(item_id),
+                    tags: required!(tags),
+                    time: time,
+                }),
+                "tags_replace" => Ok(Action::TagsReplace {
+                    item_id: required!(item_id),
+                    tags: required!(tags),
+                    time: time,
+                }),
+                "tags_clear" => Ok(Action::TagsClear {
+                    item_id: required!(item_id),
+                    time: time,
+                }),
+                "tag_rename" => Ok(Action::TagRename {
+                    old_tag: required!(old_tag),
+                    new_tag: required!(new_tag),
+                    time: time,
+                }),
+                "tag_delete" => Ok(Action::TagDelete {
+                    tag: required!(tag),
+                    time: time,
+                }),
+                _ => Err(d.error(&format!("unknown action `{}`", name))),
+            }
         })
     }
 }
 
 pub struct PocketSendRequest<'a, 'b> {
     pocket: &'b mut Pocket,
-    actions: &'a [&'a PocketAction],
+    actions: &'a [&'a dyn PocketAction],
 }
 
 impl<'a, 'b> JsonEncodable for PocketSendRequest<'a, 'b> {
@@ -897,9 +1948,7 @@ impl<'a, 'b> JsonEncodable for PocketSendRequest<'a, 'b> {
         e.emit_struct("PocketSendRequest", 3, |e| {
             e.emit_struct_field("consumer_key", 0, |e| self.pocket.consumer_key.encode(e))
                 .and_then(|_| {
-                    e.emit_struct_field("access_token", 1, |e| {
-                        self.pocket.access_token.as_ref().unwrap().encode(e)
-                    })
+                    e.emit_struct_field("access_token", 1, |e| self.pocket.access_token.encode(e))
                 })
                 .and_then(|_| {
                     e.emit_struct_field("actions", 2, |e| {
@@ -915,88 +1964,523 @@ impl<'a, 'b> JsonEncodable for PocketSendRequest<'a, 'b> {
     }
 }
 
-#[derive(RustcDecodable)]
+#[derive(Debug, PartialEq)]
+pub enum ActionOutcome {
+    Success,
+    Added(PocketAddedItem),
+    Failure { code: Option<u16>, message: String },
+}
+
+impl ActionOutcome {
+    pub fn is_success(&self) -> bool {
+        match *self {
+            ActionOutcome::Failure { .. } => false,
+            _ => true,
+        }
+    }
+
+    fn from_json(result: Json, error: Option<&Json>) -> Result<ActionOutcome, json::DecoderError> {
+        match result {
+            Json::Boolean(true) => Ok(ActionOutcome::Success),
+            Json::Boolean(false) => {
+                let error = error.and_then(|e| e.as_object());
+                Ok(ActionOutcome::Failure {
+                    code: error.and_then(|e| e.get("code")).and_then(|c| match *c {
+                        Json::U64(v) => u16::try_from(v).ok(),
+                        Json::I64(v) => u16::try_from(v).ok(),
+                        Json::String(ref v) => v.parse().ok(),
+                        _ => None,
+                    }),
+                    message: error
+                        .and_then(|e| e.get("message"))
+                        .and_then(|m| m.as_string())
+                        .unwrap_or("unknown action error")
+                        .to_string(),
+                })
+            }
+            item @ Json::Object(_) => {
+                Decodable::decode(&mut json::Decoder::new(item)).map(ActionOutcome::Added)
+            }
+            other => Err(json::DecoderError::ExpectedError(
+                "Boolean or Object".to_string(),
+                other.to_string(),
+            )),
+        }
+    }
+}
+
 pub struct PocketSendResponse {
     status: u16,
-    action_results: Vec<bool>,
+    action_results: Vec<ActionOutcome>,
+}
+
+impl PocketSendResponse {
+    fn from_str(data: &str) -> Result<PocketSendResponse, json::DecoderError> {
+        let mut response = match parse_json(data)? {
+            Json::Object(obj) => obj,
+            other => {
+                return Err(json::DecoderError::ExpectedError(
+                    "Object".to_string(),
+                    other.to_string(),
+                ))
+            }
+        };
+
+        let status = match response.remove("status") {
+            Some(v) => Decodable::decode(&mut json::Decoder::new(v))?,
+            None => return Err(json::DecoderError::MissingFieldError("status".to_string())),
+        };
+        let results = match response.remove("action_results") {
+            Some(Json::Array(v)) => v,
+            Some(other) => {
+                return Err(json::DecoderError::ExpectedError(
+                    "Array".to_string(),
+                    other.to_string(),
+                ))
+            }
+            None => {
+                return Err(json::DecoderError::MissingFieldError(
+                    "action_results".to_string(),
+                ))
+            }
+        };
+        let errors = match response.remove("action_errors") {
+            Some(Json::Array(v)) => v,
+            _ => Vec::new(),
+        };
+
+        Ok(PocketSendResponse {
+            status: status,
+            action_results: results
+                .into_iter()
+                .enumerate()
+                .map(|(i, r)| ActionOutcome::from_json(r, errors.get(i)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SendStatus {
+    Sent(ActionOutcome),
+    Failed,
+    NotAttempted,
+}
+
+#[derive(Debug)]
+pub struct SendReport {
+    pub statuses: Vec<SendStatus>,
+    pub error: Option<PocketError>,
+}
+
+impl SendReport {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+            && self.statuses.iter().all(|s| match *s {
+                SendStatus::Sent(ref outcome) => outcome.is_success(),
+                _ => false,
+            })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SyncState {
+    since: Option<Timespec>,
+    known_items: BTreeSet<u64>,
+}
+
+impl SyncState {
+    pub fn new() -> SyncState {
+        SyncState::default()
+    }
+
+    #[inline]
+    pub fn since(&self) -> Option<Timespec> {
+        self.since
+    }
+
+    #[inline]
+    pub fn is_known(&self, item_id: u64) -> bool {
+        self.known_items.contains(&item_id)
+    }
+
+    fn apply(&mut self, response: PocketSyncResponse) -> SyncDelta {
+        let mut delta = SyncDelta::default();
+        for entry in response.list {
+            match entry {
+                SyncEntry::Deleted(item_id) => {
+                    self.known_items.remove(&item_id);
+                    delta.deleted.push(item_id);
+                }
+                SyncEntry::Item(item) => match item.status {
+                    PocketItemStatus::Deleted => {
+                        self.known_items.remove(&item.item_id);
+                        delta.deleted.push(item.item_id);
+                    }
+                    PocketItemStatus::Archived => {
+                        self.known_items.insert(item.item_id);
+                        delta.archived.push(item);
+                    }
+                    PocketItemStatus::Normal | PocketItemStatus::Unknown(_) => {
+                        if self.known_items.insert(item.item_id) {
+                            delta.added.push(item);
+                        } else {
+                            delta.updated.push(item);
+                        }
+                    }
+                },
+            }
+        }
+        self.since = Some(response.since);
+        delta
+    }
+}
+
+impl Encodable for SyncState {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        e.emit_struct("SyncState", 2, |e| {
+            e.emit_struct_field("since", 0, |e| self.since.map(|v| v.sec).encode(e))
+                .and_then(|_| e.emit_struct_field("known_items", 1, |e| self.known_items.encode(e)))
+        })
+    }
+}
+
+impl Decodable for SyncState {
+    fn decode<D: Decoder>(d: &mut D) -> Result<SyncState, D::Error> {
+        d.read_struct("SyncState", 2, |d| {
+            Ok(SyncState {
+                since: d
+                    .read_struct_field("since", 0, |d| Option::<i64>::decode(d))?
+                    .map(|v| Timespec::new(v, 0)),
+                known_items: d.read_struct_field("known_items", 1, Decodable::decode)?,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SyncDelta {
+    pub added: Vec<PocketItem>,
+    pub updated: Vec<PocketItem>,
+    pub archived: Vec<PocketItem>,
+    pub deleted: Vec<u64>,
+}
+
+impl SyncDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.archived.is_empty()
+            && self.deleted.is_empty()
+    }
+}
+
+enum SyncEntry {
+    Item(PocketItem),
+    Deleted(u64),
+}
+
+struct PocketSyncResponse {
+    list: Vec<SyncEntry>,
+    since: Timespec,
+}
+
+impl PocketSyncResponse {
+    fn from_str(data: &str) -> Result<PocketSyncResponse, json::DecoderError> {
+        let mut response = match parse_json(data)? {
+            Json::Object(obj) => obj,
+            other => {
+                return Err(json::DecoderError::ExpectedError(
+                    "Object".to_string(),
+                    other.to_string(),
+                ))
+            }
+        };
+
+        let since: u64 = match response.remove("since") {
+            Some(v) => Decodable::decode(&mut json::Decoder::new(v))?,
+            None => return Err(json::DecoderError::MissingFieldError("since".to_string())),
+        };
+        // deleted items come with item_id and status only
+        let list = match response.remove("list") {
+            Some(Json::Object(list)) => list
+                .into_iter()
+                .map(|(_, item)| {
+                    let status = item.find("status").cloned().unwrap_or(Json::Null);
+                    match Decodable::decode(&mut json::Decoder::new(status))? {
+                        PocketItemStatus::Deleted => {
+                            let item_id = item.find("item_id").cloned().unwrap_or(Json::Null);
+                            Decodable::decode(&mut json::Decoder::new(item_id))
+                                .map(SyncEntry::Deleted)
+                        }
+                        _ => Decodable::decode(&mut json::Decoder::new(item)).map(SyncEntry::Item),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => Vec::new(),
+        };
+
+        Ok(PocketSyncResponse {
+            list: list,
+            since: Timespec::new(since as i64, 0),
+        })
+    }
+}
+
+fn header_error(status: u16, headers: &HeaderMap) -> Option<PocketError> {
+    let limited = ["X-Limit-User-Remaining", "X-Limit-Key-Remaining"]
+        .iter()
+        .any(|name| headers.get(*name).map_or(false, |v| v == "0"));
+    if status == 429 || (status == 403 && limited) {
+        let reset_in = RateLimits::from_headers(headers, Instant::now())
+            .and_then(|limits| limits.exhausted_for(limits.received));
+        return Some(PocketError::RateLimited { reset_in: reset_in });
+    }
+    let err = match (status, headers.get("X-Error-Code")) {
+        (503, _) => PocketApiError::Maintenance,
+        (200..=299, None) => return None,
+        (_, None) => PocketApiError::Status(status),
+        (_, Some(code)) => match code.to_str().ok().and_then(|v| v.trim().parse().ok()) {
+            Some(code) => PocketApiError::from_code(code),
+            None => return Some(PocketError::MalformedHeader("X-Error-Code".to_string())),
+        },
+    };
+    let error = headers
+        .get("X-Error")
+        .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+        .or_else(|| match err {
+            PocketApiError::Status(_) => StatusCode::from_u16(status)
+                .ok()
+                .and_then(|s| s.canonical_reason())
+                .map(str::to_string),
+            _ => None,
+        })
+        .unwrap_or_else(|| "unknown protocol error".to_string());
+    Some(PocketError::Proto(err, error))
+}
+
+// Not suitable for secrets, only used to spread retries (see `RetryPolicy::jitter`).
+fn random_u64() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(nanos);
+    hasher.finish()
+}
+
+fn post_raw(
+    client: &Client,
+    url: &str,
+    data: &str,
+    rate_limits: &mut Option<RateLimits>,
+) -> PocketResult<String> {
+    client
+        .post(url)
+        .header("X-Accept", "application/json")
+        .header("Content-Type", "application/json; charset=UTF-8")
+        .body(data.to_string())
+        .send()
+        .map_err(From::from)
+        .and_then(|mut r| {
+            if let Some(limits) = RateLimits::from_headers(r.headers(), Instant::now()) {
+                *rate_limits = Some(limits);
+            }
+            match header_error(r.status().as_u16(), r.headers()) {
+                None => {
+                    let form = r
+                        .headers()
+                        .get("Content-Type")
+                        .and_then(|v| v.to_str().ok())
+                        .map_or(false, |v| {
+                            v.starts_with("application/x-www-form-urlencoded")
+                        });
+                    let mut out = String::new();
+                    r.read_to_string(&mut out)?;
+                    Ok(if form { form_to_json(&*out) } else { out })
+                }
+                Some(err) => Err(err),
+            }
+        })
+}
+
+// The OAuth endpoints answer with a form if `X-Accept` is ignored,
+// such responses are turned into JSON objects with string values.
+fn form_to_json(form: &str) -> String {
+    let object: json::Object = form
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let key = percent_decode(parts.next().unwrap_or(""));
+            (
+                key,
+                Json::String(percent_decode(parts.next().unwrap_or(""))),
+            )
+        })
+        .collect();
+    Json::Object(object).to_string()
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'+', _) => out.push(b' '),
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 2;
+            }
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn post<Resp: Decodable>(
+    client: &Client,
+    strict: bool,
+    url: &str,
+    data: &str,
+) -> PocketResult<Resp> {
+    post_raw(client, url, data, &mut None)
+        .and_then(|s| decode_with_mode(strict, || decode_json::<Resp>(&*s)).map_err(From::from))
 }
 
 impl Pocket {
-    pub fn new(consumer_key: &str, access_token: Option<&str>) -> Pocket {
+    pub fn new(consumer_key: &str, access_token: &str) -> Pocket {
+        Pocket::with_client(consumer_key, access_token, Client::new())
+    }
+
+    fn with_client(consumer_key: &str, access_token: &str, client: Client) -> Pocket {
         Pocket {
             consumer_key: consumer_key.to_string(),
-            access_token: access_token.map(|v| v.to_string()),
-            code: None,
-            client: Client::new(),
+            access_token: access_token.to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            strict: false,
+            throttle: Throttle::Off,
+            rate_limits: None,
+            retry: RetryPolicy::default(),
+            client: client,
         }
     }
 
+    // Points the client to another server, e.g. a stand-in for tests.
     #[inline]
-    pub fn access_token(&self) -> Option<&str> {
-        self.access_token.as_ref().map(|v| &**v)
-    }
-
-    fn request<Resp: Decodable>(&mut self, url: &str, data: &str) -> PocketResult<Resp> {
-        self.client
-            .post(url)
-            .header("XAccept", "application/json")
-            .header("ContentType", "application/json")
-            .body(data.to_string())
-            .send()
-            .map_err(From::from)
-            .and_then(|mut r| match r.headers().get("XErrorCode") {
-                None => {
-                    let mut out = String::new();
-                    r.read_to_string(&mut out).map_err(From::from).map(|_| out)
-                }
-                Some(code) => {
-                    let code = code.to_str().unwrap().parse().unwrap();
-                    let error = r
-                        .headers()
-                        .get("XError")
-                        .map(|v| v.to_str().unwrap())
-                        .unwrap_or("unknown protocol error")
-                        .to_string();
-                    Err(PocketError::Proto(code, error))
-                }
-            })
-            .and_then(|s| json::decode::<Resp>(&*s).map_err(From::from))
+    pub fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = base_url.trim_end_matches('/').to_string();
     }
 
-    pub fn get_auth_url(&mut self) -> PocketResult<Url> {
-        let request = json::encode(&PocketOAuthRequest {
-            consumer_key: &*self.consumer_key,
-            redirect_uri: "rustapi:finishauth",
-            state: None,
-        })?;
+    #[inline]
+    pub fn base_url(&self) -> &str {
+        &*self.base_url
+    }
 
-        self.request("https://getpocket.com/v3/oauth/request", &*request)
-            .and_then(|r: PocketOAuthResponse| {
-                let mut url = Url::parse("https://getpocket.com/auth/authorize").unwrap();
-                url.set_query_from_pairs(
-                    vec![
-                        ("request_token", &*r.code),
-                        ("redirect_uri", "rustapi:finishauth"),
-                    ]
-                    .into_iter(),
-                );
-                self.code = Some(r.code);
-                Ok(url)
-            })
+    #[inline]
+    pub fn consumer_key(&self) -> &str {
+        &*self.consumer_key
     }
 
-    pub fn authorize(&mut self) -> PocketResult<String> {
-        let request = json::encode(&PocketAuthorizeRequest {
-            consumer_key: &*self.consumer_key,
-            code: self.code.as_ref().map(|v| &**v).unwrap(),
-        })?;
+    #[inline]
+    pub fn access_token(&self) -> &str {
+        &*self.access_token
+    }
+
+    // In strict mode values unknown to the bindings fail decoding instead of
+    // being kept as `Unknown(_)` variants.
+    #[inline]
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    #[inline]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    // Quota reported with the latest response, if the server sent one.
+    #[inline]
+    pub fn rate_limits(&self) -> Option<&RateLimits> {
+        self.rate_limits.as_ref()
+    }
+
+    #[inline]
+    pub fn set_throttle(&mut self, throttle: Throttle) {
+        self.throttle = throttle;
+    }
+
+    fn wait_for_quota(&self) -> PocketResult<()> {
+        let reset_in = match (self.throttle, self.rate_limits) {
+            (Throttle::Off, _) | (_, None) => None,
+            (_, Some(limits)) => limits.exhausted_for(Instant::now()),
+        };
+        match (self.throttle, reset_in) {
+            (Throttle::Sleep, Some(reset_in)) => {
+                thread::sleep(reset_in);
+                Ok(())
+            }
+            (Throttle::Fail, Some(reset_in)) => Err(PocketError::RateLimited {
+                reset_in: Some(reset_in),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    #[inline]
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    fn request<Resp: Decodable>(
+        &mut self,
+        method: &str,
+        data: &str,
+        idempotent: bool,
+    ) -> PocketResult<Resp> {
+        let strict = self.strict;
+        self.request_raw(method, data, idempotent)
+            .and_then(|s| decode_with_mode(strict, || decode_json::<Resp>(&*s)).map_err(From::from))
+    }
 
-        match self.request("https://getpocket.com/v3/oauth/authorize", &*request) {
-            Ok(r @ PocketAuthorizeResponse { .. }) => {
-                self.access_token = Some(r.access_token);
-                Ok(r.username)
+    fn request_raw(&mut self, method: &str, data: &str, idempotent: bool) -> PocketResult<String> {
+        if self.access_token.is_empty() {
+            return Err(PocketError::NotAuthenticated);
+        }
+        let url = format!("{}/v3/{}", self.base_url, method);
+        let mut attempt = 1;
+        loop {
+            self.wait_for_quota()?;
+            match post_raw(&self.client, &*url, data, &mut self.rate_limits) {
+                Err(ref err) if self.retry.should_retry(attempt, idempotent, err) => {
+                    self.retry.wait(attempt, err);
+                    attempt += 1;
+                }
+                result => return result,
             }
-            Err(e) => Err(e),
         }
     }
 
@@ -1004,19 +2488,19 @@ impl Pocket {
         &mut self,
         url: &str,
         title: Option<&str>,
-        tags: Option<&str>,
+        tags: Option<&TagSet>,
         tweet_id: Option<&str>,
     ) -> PocketResult<PocketAddedItem> {
         let request = json::encode(&PocketAddRequest {
             consumer_key: &*self.consumer_key,
-            access_token: &**self.access_token.as_ref().unwrap(),
+            access_token: &*self.access_token,
             url: url,
             title: title.map(|v| v.clone()),
-            tags: tags.map(|v| v.clone()),
+            tags: tags,
             tweet_id: tweet_id.map(|v| v.clone()),
         })?;
 
-        self.request("https://getpocket.com/v3/add", &*request)
+        self.request("add", &*request, false)
             .map(|v: PocketAddResponse| v.item)
     }
 
@@ -1026,13 +2510,151 @@ impl Pocket {
     }
 
     pub fn filter(&mut self) -> PocketGetRequest {
-        PocketGetRequest::new(self)
+        PocketGetRequest::new(self, Query::new())
+    }
+
+    pub fn filter_with(&mut self, query: Query) -> PocketGetRequest {
+        PocketGetRequest::new(self, query)
+    }
+
+    pub fn get(&mut self, query: &Query) -> PocketResult<Vec<PocketItem>> {
+        self.filter_with(query.clone()).get()
+    }
+
+    pub fn get_compound(
+        &mut self,
+        query: &Query,
+        filter: &TagFilter,
+    ) -> PocketResult<Vec<PocketItem>> {
+        compound::run(query, filter, |q| self.get(&q))
+    }
+
+    pub fn search(&mut self, search: &Search) -> PocketResult<Vec<PocketItem>> {
+        search
+            .request(self)
+            .get()
+            .map(|items| items.into_iter().filter(|i| search.matches(i)).collect())
+    }
+
+    pub fn sync(&mut self, state: &mut SyncState) -> PocketResult<SyncDelta> {
+        let request = {
+            let mut f = self.filter();
+            f.state(PocketGetState::All).complete();
+            if let Some(since) = state.since {
+                f.since(since);
+            }
+            f.to_json()?
+        };
+
+        let strict = self.strict;
+        self.request_raw("get", &*request, true)
+            .and_then(|s| {
+                decode_with_mode(strict, || PocketSyncResponse::from_str(&*s)).map_err(From::from)
+            })
+            .map(|r| state.apply(r))
+    }
+
+    pub fn send(&mut self, actions: &[&dyn PocketAction]) -> PocketResult<Vec<ActionOutcome>> {
+        let mut request = String::new();
+        {
+            let data = PocketSendRequest {
+                pocket: self,
+                actions: actions,
+            };
+            let mut encoder = json::Encoder::new(&mut request);
+            data.json_encode(&mut encoder)?;
+        }
+
+        // a retried `add` action could add the item twice
+        let idempotent = actions.iter().all(|action| action.name() != "add");
+        let strict = self.strict;
+        self.request_raw("send", &*request, idempotent)
+            .and_then(|s| {
+                decode_with_mode(strict, || PocketSendResponse::from_str(&*s)).map_err(From::from)
+            })
+            .map(|v| v.action_results)
+    }
+
+    fn send_action(&mut self, action: &dyn PocketAction) -> PocketResult<ActionOutcome> {
+        match self.send(&[action])?.pop() {
+            Some(ActionOutcome::Failure { code, message }) => Err(PocketError::Proto(
+                code.map_or(PocketApiError::ActionFailed, PocketApiError::from_code),
+                message,
+            )),
+            Some(outcome) => Ok(outcome),
+            None => Err(PocketError::Json(json::DecoderError::MissingFieldError(
+                "action_results".to_string(),
+            ))),
+        }
+    }
+
+    pub fn rename_tag(&mut self, old_tag: &Tag, new_tag: &Tag) -> PocketResult<()> {
+        self.send_action(&PocketTagRenameAction::new(old_tag, new_tag))
+            .map(|_| ())
+    }
+
+    pub fn delete_tag(&mut self, tag: &Tag) -> PocketResult<()> {
+        self.send_action(&PocketTagDeleteAction::new(tag))
+            .map(|_| ())
+    }
+
+    pub fn add_tags(&mut self, item_id: u64, tags: &TagSet) -> PocketResult<()> {
+        self.send_action(&PocketTagsAddAction::new(item_id, tags))
+            .map(|_| ())
+    }
+
+    pub fn remove_tags(&mut self, item_id: u64, tags: &TagSet) -> PocketResult<()> {
+        self.send_action(&PocketTagsRemoveAction::new(item_id, tags))
+            .map(|_| ())
+    }
+
+    pub fn replace_tags(&mut self, item_id: u64, tags: &TagSet) -> PocketResult<()> {
+        self.send_action(&PocketTagsReplaceAction::new(item_id, tags))
+            .map(|_| ())
+    }
+
+    pub fn clear_tags(&mut self, item_id: u64) -> PocketResult<()> {
+        self.send_action(&PocketTagsClearAction::new(item_id))
+            .map(|_| ())
+    }
+
+    pub fn send_all(&mut self, actions: &[&dyn PocketAction], chunk_size: usize) -> SendReport {
+        let chunk_size = cmp::max(chunk_size, 1);
+        let mut report = SendReport {
+            statuses: actions.iter().map(|_| SendStatus::NotAttempted).collect(),
+            error: None,
+        };
+
+        for (n, chunk) in actions.chunks(chunk_size).enumerate() {
+            let start = n * chunk_size;
+            let statuses = &mut report.statuses[start..start + chunk.len()];
+            match self.send(chunk) {
+                Ok(results) => {
+                    let mut results = results.into_iter();
+                    for status in statuses.iter_mut() {
+                        *status = match results.next() {
+                            Some(outcome) => SendStatus::Sent(outcome),
+                            None => SendStatus::Failed,
+                        };
+                    }
+                }
+                Err(e) => {
+                    for status in statuses.iter_mut() {
+                        *status = SendStatus::Failed;
+                    }
+                    report.error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        report
     }
 }
 
 #[test]
 fn test_actions_serialize() {
-    let mut pocket = Pocket::new("abc", Some("def"));
+    let mut pocket = Pocket::new("abc", "def");
     let add_action = PocketAddAction {
         item_id: None,
         ref_id: None,
@@ -1050,3 +2672,567 @@ fn test_actions_serialize() {
 
     //}");
 }
+
+#[test]
+fn test_action_constructors_serialize() {
+    let mut out = String::new();
+    {
+        let mut encoder = json::Encoder::new(&mut out);
+        PocketArchiveAction::new(1234)
+            .at(1500000000)
+            .json_encode(&mut encoder)
+            .unwrap();
+    }
+    assert_eq!(
+        out,
+        r#"{"name":"archive","item_id":1234,"time":1500000000}"#
+    );
+
+    let mut out = String::new();
+    {
+        let mut encoder = json::Encoder::new(&mut out);
+        PocketAddAction::new("http://example.com")
+            .title("Example")
+            .tags(&"b, a".parse().unwrap())
+            .json_encode(&mut encoder)
+            .unwrap();
+    }
+    assert_eq!(
+        out,
+        r#"{"name":"add","item_id":null,"ref_id":null,"tags":"a,b","time":null,"title":"Example","url":"http://example.com"}"#
+    );
+}
+
+#[test]
+fn test_action_roundtrip() {
+    let actions = vec![
+        Action::Add {
+            item_id: None,
+            ref_id: None,
+            tags: Some("a,b".parse().unwrap()),
+            time: None,
+            title: None,
+            url: Some("http://example.com".to_string()),
+        },
+        Action::Archive {
+            item_id: 1,
+            time: Some(1500000000),
+        },
+        Action::TagsRemove {
+            item_id: 2,
+            tags: "a".parse().unwrap(),
+            time: None,
+        },
+        Action::TagRename {
+            old_tag: Tag::new("a").unwrap(),
+            new_tag: Tag::new("b").unwrap(),
+            time: None,
+        },
+        Action::TagDelete {
+            tag: Tag::new("b").unwrap(),
+            time: None,
+        },
+    ];
+
+    let encoded = json::encode(&actions).unwrap();
+    let decoded: Vec<Action> = json::decode(&encoded).unwrap();
+    assert_eq!(decoded, actions);
+
+    let decoded: Action = json::decode(r#"{"name":"favorite","item_id":"42"}"#).unwrap();
+    assert_eq!(
+        decoded,
+        Action::Favorite {
+            item_id: 42,
+            time: None
+        }
+    );
+    assert!(json::decode::<Action>(r#"{"name":"archive"}"#).is_err());
+    assert!(json::decode::<Action>(r#"{"name":"explode","item_id":1}"#).is_err());
+}
+
+#[cfg(test)]
+const ADDED_ITEM_JSON: &str = r#"{
+    "item_id": "229279689",
+    "extended_item_id": "229279689",
+    "given_url": "http://example.com/article",
+    "normal_url": "http://example.com/article",
+    "content_length": "1024",
+    "word_count": "200",
+    "encoding": "utf-8",
+    "mime_type": "text/html",
+    "lang": "en",
+    "title": "Example",
+    "excerpt": "An example article",
+    "date_published": "2015-01-01 00:00:00",
+    "date_resolved": "2015-01-02 00:00:00",
+    "resolved_id": "229279689",
+    "resolved_url": "http://example.com/article",
+    "resolved_normal_url": "http://example.com/article",
+    "login_required": "0",
+    "response_code": "200",
+    "used_fallback": "0",
+    "domain_id": "1",
+    "origin_domain_id": "1",
+    "innerdomain_redirect": "0",
+    "is_index": "0",
+    "is_article": "1",
+    "has_image": "0",
+    "has_video": "0",
+    "videos": [],
+    "images": [],
+    "authors": {
+        "12": {"item_id": "229279689", "author_id": "12", "name": "Jane Doe", "url": "http://example.com/jane"}
+    }
+}"#;
+
+#[test]
+fn test_send_response_decode() {
+    let response = format!(
+        r#"{{
+            "status": 1,
+            "action_results": [true, false, {}],
+            "action_errors": [null, {{"message": "Invalid item id", "type": "Bad Request", "code": 422}}, null]
+        }}"#,
+        ADDED_ITEM_JSON
+    );
+    let response = PocketSendResponse::from_str(&response).unwrap();
+    assert_eq!(response.status, 1);
+    assert_eq!(response.action_results.len(), 3);
+    assert_eq!(response.action_results[0], ActionOutcome::Success);
+    assert_eq!(
+        response.action_results[1],
+        ActionOutcome::Failure {
+            code: Some(422),
+            message: "Invalid item id".to_string(),
+        }
+    );
+    match response.action_results[2] {
+        ActionOutcome::Added(ref item) => assert_eq!(item.item_id, 229279689),
+        ref other => panic!("unexpected outcome {:?}", other),
+    }
+
+    let response = PocketSendResponse::from_str(r#"{"status": 1, "action_results": [false]}"#);
+    assert!(!response.unwrap().action_results[0].is_success());
+
+    // codes out of range are dropped rather than wrapped around
+    let response = PocketSendResponse::from_str(
+        r#"{"status": 1, "action_results": [false, false],
+            "action_errors": [{"message": "a", "code": 65958}, {"message": "b", "code": -1}]}"#,
+    )
+    .unwrap();
+    for outcome in response.action_results {
+        match outcome {
+            ActionOutcome::Failure { code, .. } => assert_eq!(code, None),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_tag_actions_serialize() {
+    let mut pocket = Pocket::new("abc", "def");
+    let (rust, rustlang, old) = (
+        Tag::new("rust").unwrap(),
+        Tag::new("rustlang").unwrap(),
+        Tag::new("old").unwrap(),
+    );
+    let (ab, a, c) = (
+        "a,b".parse().unwrap(),
+        "a".parse().unwrap(),
+        "c".parse().unwrap(),
+    );
+    let rename = PocketTagRenameAction::new(&rust, &rustlang);
+    let delete = PocketTagDeleteAction::new(&old).at(1500000000);
+    let add = PocketTagsAddAction::new(1, &ab);
+    let remove = PocketTagsRemoveAction::new(2, &a);
+    let replace = PocketTagsReplaceAction::new(3, &c);
+    let clear = PocketTagsClearAction::new(4);
+    let actions: &[&dyn PocketAction] = &[&rename, &delete, &add, &remove, &replace, &clear];
+    let request = PocketSendRequest {
+        pocket: &mut pocket,
+        actions: actions,
+    };
+
+    let mut out = String::new();
+    {
+        let mut encoder = json::Encoder::new(&mut out);
+        request.json_encode(&mut encoder).unwrap();
+    }
+    assert_eq!(
+        out,
+        concat!(
+            r#"{"consumer_key":"abc","access_token":"def","actions":["#,
+            r#"{"name":"tag_rename","old_tag":"rust","new_tag":"rustlang","time":null},"#,
+            r#"{"name":"tag_delete","tag":"old","time":1500000000},"#,
+            r#"{"name":"tags_add","item_id":1,"tags":"a,b","time":null},"#,
+            r#"{"name":"tags_remove","item_id":2,"tags":"a","time":null},"#,
+            r#"{"name":"tags_replace","item_id":3,"tags":"c","time":null},"#,
+            r#"{"name":"tags_clear","item_id":4,"time":null}"#,
+            r#"]}"#
+        )
+    );
+}
+
+#[test]
+fn test_tag_validation() {
+    assert_eq!(Tag::new("  rust ").unwrap().as_str(), "rust");
+    assert!(Tag::new("").is_err());
+    assert!(Tag::new("   ").is_err());
+    assert!(Tag::new("a,b").is_err());
+
+    let tags: TagSet = " rust, async ,rust,,".parse().unwrap();
+    assert_eq!(tags.len(), 2);
+    assert!(tags.contains(&Tag::new("async").unwrap()));
+    assert_eq!(tags.to_string(), "async,rust");
+    assert_eq!(json::encode(&tags).unwrap(), r#""async,rust""#);
+    assert_eq!(json::decode::<TagSet>(r#""rust,async""#).unwrap(), tags);
+    assert!(json::decode::<Tag>(r#""a,b""#).is_err());
+}
+
+#[cfg(test)]
+const ITEM_JSON: &str = r#"{
+    "item_id": "229279689",
+    "resolved_id": "229279689",
+    "given_url": "http://example.com/article",
+    "given_title": "Example",
+    "favorite": "0",
+    "status": "0",
+    "time_added": "1500000000",
+    "time_updated": "1500000100",
+    "time_read": "0",
+    "time_favorited": "0",
+    "sort_id": 0,
+    "resolved_title": "Example",
+    "resolved_url": "http://example.com/article",
+    "excerpt": "An example article",
+    "is_article": "1",
+    "is_index": "0",
+    "has_video": "0",
+    "has_image": "0",
+    "word_count": "200",
+    "tags": {
+        "rust": {"item_id": "229279689", "tag": "rust"},
+        "async": {"item_id": "229279689", "tag": "async"}
+    },
+    "authors": {
+        "12": {"item_id": "229279689", "author_id": "12", "name": "Jane Doe", "url": "http://example.com/jane"}
+    }
+}"#;
+
+#[test]
+fn test_item_tags_authors_decode() {
+    let item: PocketItem = json::decode(ITEM_JSON).unwrap();
+    assert_eq!(item.tags.len(), 2);
+    assert_eq!(item.tag_set(), "rust,async".parse().unwrap());
+    assert_eq!(
+        item.authors,
+        vec![ItemAuthor {
+            item_id: 229279689,
+            author_id: 12,
+            name: "Jane Doe".to_string(),
+            url: "http://example.com/jane".to_string(),
+        }]
+    );
+
+    let item: PocketAddedItem = decode_json(ADDED_ITEM_JSON).unwrap();
+    assert!(item.tags.is_empty());
+    assert!(item.videos.is_empty());
+    assert_eq!(item.authors.len(), 1);
+    assert_eq!(item.authors[0].name, "Jane Doe");
+
+    // a malformed entry fails the whole item instead of being dropped
+    let bad_tag = ITEM_JSON.replace(r#""tag": "async""#, r#""tag": "a,b""#);
+    assert!(json::decode::<PocketItem>(&*bad_tag).is_err());
+    let bad_author = ADDED_ITEM_JSON.replace(r#""author_id": "12""#, r#""author_id": "x""#);
+    assert!(decode_json::<PocketAddedItem>(&*bad_author).is_err());
+    let not_a_list = ITEM_JSON.replace(
+        r#""is_index": "0","#,
+        r#""is_index": "0", "images": "none","#,
+    );
+    assert!(decode_json::<PocketItem>(&*not_a_list).is_err());
+}
+
+#[test]
+fn test_get_response_decode() {
+    let response = format!(
+        r#"{{
+            "status": 1,
+            "complete": 1,
+            "error": null,
+            "search_meta": {{"search_type": "normal"}},
+            "since": 1500000200,
+            "list": {{"229279689": {}}}
+        }}"#,
+        ITEM_JSON
+    );
+    let response: PocketGetResponse = json::decode(&response).unwrap();
+    assert_eq!(response.list.len(), 1);
+    assert!(response.complete);
+    assert_eq!(response.error, None);
+    assert_eq!(
+        response.search_meta.unwrap().search_type,
+        Some("normal".to_string())
+    );
+    assert_eq!(response.since, Timespec::new(1500000200, 0));
+
+    let response: PocketGetResponse = decode_json(
+        r#"{"status": 2, "complete": 1, "error": null, "since": 1500000200, "list": []}"#,
+    )
+    .unwrap();
+    assert!(response.list.is_empty());
+    assert_eq!(response.search_meta, None);
+
+    // one malformed item fails the response instead of going missing
+    let response = format!(
+        r#"{{"status": 1, "complete": 1, "since": 1500000200,
+            "list": {{"229279689": {}, "229279690": {}}}}}"#,
+        ITEM_JSON,
+        ITEM_JSON.replace(
+            r#""time_added": "1500000000""#,
+            r#""time_added": "yesterday""#
+        )
+    );
+    assert!(decode_json::<PocketGetResponse>(&response).is_err());
+}
+
+#[test]
+fn test_sync_state_apply() {
+    let mut state = SyncState::new();
+    let response = format!(
+        r#"{{"status": 1, "complete": 1, "since": 1500000200, "list": {{"229279689": {}}}}}"#,
+        ITEM_JSON
+    );
+    let delta = state.apply(PocketSyncResponse::from_str(&response).unwrap());
+    assert_eq!(delta.added.len(), 1);
+    assert!(delta.updated.is_empty());
+    assert!(state.is_known(229279689));
+    assert_eq!(state.since(), Some(Timespec::new(1500000200, 0)));
+
+    let response = format!(
+        r#"{{"status": 1, "complete": 1, "since": 1500000300, "list": {{"229279689": {}}}}}"#,
+        ITEM_JSON
+    );
+    let delta = state.apply(PocketSyncResponse::from_str(&response).unwrap());
+    assert!(delta.added.is_empty());
+    assert_eq!(delta.updated.len(), 1);
+
+    let encoded = json::encode(&state).unwrap();
+    assert_eq!(json::decode::<SyncState>(&encoded).unwrap(), state);
+
+    let response = r#"{"status": 1, "complete": 1, "since": 1500000400,
+        "list": {"229279689": {"item_id": "229279689", "status": "2"}}}"#;
+    let delta = state.apply(PocketSyncResponse::from_str(response).unwrap());
+    assert_eq!(delta.deleted, vec![229279689]);
+    assert!(!state.is_known(229279689));
+    assert_eq!(state.since(), Some(Timespec::new(1500000400, 0)));
+
+    let response = r#"{"status": 2, "complete": 1, "since": 1500000500, "list": []}"#;
+    assert!(state
+        .apply(PocketSyncResponse::from_str(response).unwrap())
+        .is_empty());
+}
+
+#[test]
+fn test_query_roundtrip() {
+    let mut query = Query::new();
+    query
+        .tag(PocketGetTag::Tagged(Tag::new("rust").unwrap()))
+        .unread()
+        .domain("github.com")
+        .sort_by_oldest()
+        .slice(10, 20);
+
+    let encoded = json::encode(&query).unwrap();
+    assert_eq!(
+        encoded,
+        concat!(
+            r#"{"search":null,"domain":"github.com","tag":"rust","state":"unread","#,
+            r#""content_type":null,"detail_type":null,"favorite":null,"since":null,"#,
+            r#""sort":"oldest","count":20,"offset":10}"#
+        )
+    );
+    assert_eq!(json::decode::<Query>(&encoded).unwrap(), query);
+
+    let query: Query = json::decode(r#"{"tag": "_untagged_", "state": "archive"}"#).unwrap();
+    let mut expected = Query::new();
+    expected.tag(PocketGetTag::Untagged).archived();
+    assert_eq!(query, expected);
+    assert!(json::decode::<Query>(r#"{"sort": "random"}"#).is_err());
+
+    let mut pocket = Pocket::new("abc", "def");
+    let request = pocket.filter_with(query);
+    assert_eq!(
+        json::encode(&request).unwrap(),
+        concat!(
+            r#"{"consumer_key":"abc","access_token":"def","search":null,"domain":null,"#,
+            r#""tag":"_untagged_","state":"archive","content_type":null,"detail_type":null,"#,
+            r#""favorite":null,"since":null,"sort":null,"count":null,"offset":null}"#
+        )
+    );
+}
+
+#[test]
+fn test_unknown_values_decode() {
+    let json = ITEM_JSON
+        .replace(r#""status": "0""#, r#""status": "3""#)
+        .replace(r#""has_video": "0""#, r#""has_video": "7""#);
+
+    let item: PocketItem = json::decode(&*json).unwrap();
+    assert_eq!(item.status, PocketItemStatus::Unknown(3));
+    assert_eq!(item.has_video, PocketItemHas::Unknown(7));
+
+    let result = decode_with_mode(true, || json::decode::<PocketItem>(&*json));
+    assert!(result.is_err());
+    assert!(!STRICT_DECODING.with(|s| s.get()));
+
+    // the same goes for items in a list
+    let response = format!(
+        r#"{{"status": 1, "complete": 1, "since": 1500000200, "list": {{"229279689": {}}}}}"#,
+        json
+    );
+    let lenient = decode_with_mode(false, || decode_json::<PocketGetResponse>(&*response));
+    assert_eq!(
+        lenient.unwrap().list[0].status,
+        PocketItemStatus::Unknown(3)
+    );
+    let strict = decode_with_mode(true, || decode_json::<PocketGetResponse>(&*response));
+    assert!(strict.is_err());
+
+    let video = r#"{"item_id": "1", "video_id": "1", "src": "", "width": "0", "height": "0", "vid": "", "type": "42"}"#;
+    let video: ItemVideo = json::decode(video).unwrap();
+    assert_eq!(video.vtype, ItemVideoType::Unknown(42));
+}
+
+#[test]
+fn test_unauthenticated_calls_fail() {
+    fn not_authenticated<T>(result: PocketResult<T>) -> bool {
+        match result {
+            Err(PocketError::NotAuthenticated) => true,
+            _ => false,
+        }
+    }
+
+    // an empty token fails every call without sending anything
+    let mut pocket = Pocket::new("abc", "");
+    pocket.set_base_url("http://127.0.0.1:9");
+    let tag = Tag::new("rust").unwrap();
+    let tags = TagSet::from(tag.clone());
+    let archive = PocketArchiveAction::new(1);
+    let mut state = SyncState::new();
+    assert!(not_authenticated(pocket.push("http://example.com")));
+    assert!(not_authenticated(pocket.add(
+        "http://example.com",
+        Some("title"),
+        Some(&tags),
+        None
+    )));
+    assert!(not_authenticated(pocket.filter().get()));
+    assert!(not_authenticated(pocket.filter().get_with_meta()));
+    assert!(not_authenticated(pocket.filter().pages(10).next().unwrap()));
+    assert!(not_authenticated(pocket.filter().iter().next().unwrap()));
+    assert!(not_authenticated(pocket.get(&Query::new())));
+    assert!(not_authenticated(
+        pocket.get_compound(&Query::new(), &TagFilter::tagged(tag.clone()))
+    ));
+    assert!(not_authenticated(
+        pocket.search(&"tag:rust".parse().unwrap())
+    ));
+    assert!(not_authenticated(pocket.sync(&mut state)));
+    assert!(not_authenticated(pocket.send(&[&archive])));
+    assert!(not_authenticated(pocket.rename_tag(&tag, &tag)));
+    assert!(not_authenticated(pocket.delete_tag(&tag)));
+    assert!(not_authenticated(pocket.add_tags(1, &tags)));
+    assert!(not_authenticated(pocket.remove_tags(1, &tags)));
+    assert!(not_authenticated(pocket.replace_tags(1, &tags)));
+    assert!(not_authenticated(pocket.clear_tags(1)));
+    assert!(state.since().is_none());
+
+    let report = pocket.send_all(&[&archive, &archive], 1);
+    assert!(not_authenticated(report.error.map_or(Ok(()), Err)));
+    assert_eq!(report.statuses[1], SendStatus::NotAttempted);
+}
+
+#[test]
+fn test_error_headers() {
+    use reqwest::header::HeaderValue;
+
+    let mut headers = HeaderMap::new();
+    assert!(header_error(200, &headers).is_none());
+
+    headers.insert("X-Error-Code", HeaderValue::from_static("107"));
+    headers.insert(
+        "X-Error",
+        HeaderValue::from_bytes(b"Invalid \xff token").unwrap(),
+    );
+    match header_error(401, &headers) {
+        Some(PocketError::Proto(PocketApiError::InvalidAccessToken, ref msg)) => {
+            assert_eq!(msg, "Invalid \u{fffd} token")
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
+
+    // an error status without a code isn't read as a response
+    match header_error(404, &HeaderMap::new()) {
+        Some(PocketError::Proto(PocketApiError::Status(404), ref msg)) => {
+            assert_eq!(msg, "Not Found")
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(
+        PocketApiError::Status(502).to_string(),
+        "unexpected HTTP status 502"
+    );
+    assert!(PocketApiError::Status(502).is_retryable());
+    assert!(!PocketApiError::Status(404).is_retryable());
+
+    headers.insert("X-Error-Code", HeaderValue::from_static("oops"));
+    match header_error(400, &headers) {
+        Some(PocketError::MalformedHeader(ref name)) => assert_eq!(name, "X-Error-Code"),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn test_api_errors() {
+    use reqwest::header::HeaderValue;
+
+    let err = PocketApiError::from_code(158);
+    assert_eq!(err, PocketApiError::UserRejectedCode);
+    assert!(err.is_auth_error() && !err.is_retryable());
+    assert_eq!(err.to_string(), "user rejected code (code 158)");
+
+    let err = PocketApiError::from_code(4242);
+    assert_eq!(err, PocketApiError::Unknown(4242));
+    assert_eq!(err.code(), Some(4242));
+    assert!(!err.is_auth_error() && !err.is_retryable());
+
+    let mut headers = HeaderMap::new();
+    headers.insert("X-Error-Code", HeaderValue::from_static("199"));
+    headers.insert("X-Limit-User-Remaining", HeaderValue::from_static("0"));
+    let api_error = |status, headers: &HeaderMap| match header_error(status, headers) {
+        Some(PocketError::Proto(err, _)) => err,
+        r => panic!("unexpected result: {:?}", r),
+    };
+    assert_eq!(api_error(503, &headers), PocketApiError::Maintenance);
+    assert_eq!(api_error(500, &headers), PocketApiError::ServerError);
+
+    // rate limits from the server come as the same error as from the throttle
+    match header_error(403, &headers) {
+        Some(PocketError::RateLimited { reset_in: None }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    headers.insert("X-Limit-User-Reset", HeaderValue::from_static("60"));
+    let err = header_error(429, &headers).unwrap();
+    match err {
+        PocketError::RateLimited { reset_in } => {
+            assert_eq!(reset_in, Some(Duration::from_secs(60)))
+        }
+        ref r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(err.api_error(), Some(PocketApiError::RateLimited));
+    assert!(err.is_retryable() && !err.is_auth_error());
+    assert_eq!(PocketApiError::RateLimited.code(), None);
+    assert!(crate::is_transient(&PocketError::RateLimited {
+        reset_in: None
+    }));
+}
@@ -0,0 +1,157 @@
+use crate::{Pocket, PocketAuth, PocketResult};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+const CALLBACK_PATH: &str = "/pocket/callback";
+
+// Short-lived HTTP listener on 127.0.0.1, used as the OAuth redirect URI.
+pub struct LoopbackServer {
+    listener: TcpListener,
+    state: String,
+    timeout: Option<Duration>,
+}
+
+impl LoopbackServer {
+    pub fn bind() -> PocketResult<LoopbackServer> {
+        LoopbackServer::bind_port(0)
+    }
+
+    pub fn bind_port(port: u16) -> PocketResult<LoopbackServer> {
+        Ok(LoopbackServer {
+            listener: TcpListener::bind(("127.0.0.1", port))?,
+            state: random_state()?,
+            timeout: None,
+        })
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> LoopbackServer {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    #[inline]
+    pub fn local_addr(&self) -> PocketResult<SocketAddr> {
+        self.listener.local_addr().map_err(From::from)
+    }
+
+    // The state is part of the redirect URI, as Pocket doesn't add anything to it.
+    pub fn redirect_uri(&self) -> PocketResult<String> {
+        Ok(format!(
+            "http://{}{}?state={}",
+            self.local_addr()?,
+            CALLBACK_PATH,
+            self.state
+        ))
+    }
+
+    // Waits for the browser to be redirected back. Requests to other paths
+    // or with a wrong state are answered with an error and ignored.
+    pub fn wait(&self) -> PocketResult<()> {
+        let deadline = self.timeout.map(|t| Instant::now() + t);
+        self.listener.set_nonblocking(deadline.is_some())?;
+
+        loop {
+            let mut stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if deadline.map_or(false, |d| Instant::now() >= d) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "no OAuth redirect received",
+                        )
+                        .into());
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let done = match read_target(&mut stream) {
+                Ok(Some(ref target)) if self.is_callback(target) => respond(
+                    &mut stream,
+                    "200 OK",
+                    "Pocket access granted, you can close this window.",
+                )
+                .map(|_| true),
+                Ok(Some(_)) => respond(&mut stream, "404 Not Found", "Not found.").map(|_| false),
+                Ok(None) => respond(&mut stream, "400 Bad Request", "Bad request.").map(|_| false),
+                Err(e) => Err(e),
+            };
+            // a broken connection from the browser is not fatal
+            if let Ok(true) = done {
+                return Ok(());
+            }
+        }
+    }
+
+    fn is_callback(&self, target: &str) -> bool {
+        let mut parts = target.splitn(2, '?');
+        let path = parts.next().unwrap_or("");
+        let query = parts.next().unwrap_or("");
+        path == CALLBACK_PATH
+            && query
+                .split('&')
+                .any(|pair| pair == &*format!("state={}", self.state))
+    }
+}
+
+impl PocketAuth {
+    // One-step login: `open` is given the auth URL to show or open in a browser.
+    pub fn authorize_with_loopback<F: FnOnce(&Url)>(
+        &self,
+        server: &LoopbackServer,
+        open: F,
+    ) -> PocketResult<(Pocket, String)> {
+        let auth = self.clone().redirect_uri(&*server.redirect_uri()?);
+        let pending = auth.request_authorization()?;
+        open(&pending.auth_url);
+        server.wait()?;
+        auth.authorize(&pending)
+    }
+}
+
+// The state guards the callback against forged redirects, so it comes from the OS RNG.
+fn random_state() -> io::Result<String> {
+    let mut buf = [0; 16];
+    getrandom::getrandom(&mut buf)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    Ok(buf.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Returns the request target of a GET request, or None if it isn't one.
+fn read_target(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") && request.len() < 8192 {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut line = request.lines().next().unwrap_or("").split(' ');
+    Ok(match (line.next(), line.next()) {
+        (Some("GET"), Some(target)) => Some(target.to_string()),
+        _ => None,
+    })
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,98 @@
+use reqwest::header::HeaderMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RateLimit {
+    pub limit: Option<u32>,
+    pub remaining: u32,
+    // counted from the time the limits were received
+    pub reset: Duration,
+}
+
+// The quota reported with the latest response, per user and per consumer key.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RateLimits {
+    pub user: Option<RateLimit>,
+    pub key: Option<RateLimit>,
+    pub received: Instant,
+}
+
+impl RateLimits {
+    pub(crate) fn from_headers(headers: &HeaderMap, received: Instant) -> Option<RateLimits> {
+        let user = read_limit(headers, "User");
+        let key = read_limit(headers, "Key");
+        if user.is_none() && key.is_none() {
+            return None;
+        }
+        Some(RateLimits {
+            user: user,
+            key: key,
+            received: received,
+        })
+    }
+
+    // Time left until the exhausted quotas are reset, if any is exhausted.
+    pub fn exhausted_for(&self, now: Instant) -> Option<Duration> {
+        [self.user, self.key]
+            .iter()
+            .filter_map(|limit| *limit)
+            .filter(|limit| limit.remaining == 0)
+            .filter_map(|limit| (self.received + limit.reset).checked_duration_since(now))
+            .filter(|reset_in| *reset_in > Duration::from_secs(0))
+            .max()
+    }
+}
+
+fn read_limit(headers: &HeaderMap, scope: &str) -> Option<RateLimit> {
+    let value = |name: &str| {
+        headers
+            .get(&*format!("X-Limit-{}-{}", scope, name))
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u32>().ok())
+    };
+    Some(RateLimit {
+        limit: value("Limit"),
+        remaining: value("Remaining")?,
+        reset: Duration::from_secs(value("Reset").unwrap_or(0) as u64),
+    })
+}
+
+// What to do before a request once a quota is exhausted.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Throttle {
+    Off,
+    Sleep,
+    Fail,
+}
+
+#[test]
+fn test_rate_limits() {
+    use reqwest::header::HeaderValue;
+
+    let now = Instant::now();
+    let mut headers = HeaderMap::new();
+    assert_eq!(RateLimits::from_headers(&headers, now), None);
+
+    headers.insert("X-Limit-User-Limit", HeaderValue::from_static("320"));
+    headers.insert("X-Limit-User-Remaining", HeaderValue::from_static("12"));
+    headers.insert("X-Limit-User-Reset", HeaderValue::from_static("1800"));
+    headers.insert("X-Limit-Key-Remaining", HeaderValue::from_static("0"));
+    headers.insert("X-Limit-Key-Reset", HeaderValue::from_static("60"));
+    let limits = RateLimits::from_headers(&headers, now).unwrap();
+    assert_eq!(
+        limits.user,
+        Some(RateLimit {
+            limit: Some(320),
+            remaining: 12,
+            reset: Duration::from_secs(1800),
+        })
+    );
+    assert_eq!(limits.key.map(|l| l.limit), Some(None));
+
+    assert_eq!(limits.exhausted_for(now), Some(Duration::from_secs(60)));
+    assert_eq!(
+        limits.exhausted_for(now + Duration::from_secs(45)),
+        Some(Duration::from_secs(15))
+    );
+    assert_eq!(limits.exhausted_for(now + Duration::from_secs(60)), None);
+}
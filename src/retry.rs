@@ -0,0 +1,183 @@
+use crate::{random_u64, PocketError};
+use std::cmp;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Source of the delays between attempts, replaceable in tests.
+pub trait Clock: Send + Sync {
+    fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+// Retries failed requests with exponential backoff. Calls which could create
+// duplicates (`add`, or `send` with an `add` action) are only retried on opt-in.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: f64,
+    non_idempotent: bool,
+    retry_on: Arc<dyn Fn(&PocketError) -> bool + Send + Sync>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: 0.2,
+            non_idempotent: false,
+            retry_on: Arc::new(is_transient),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl RetryPolicy {
+    #[inline]
+    pub fn new() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::default().max_attempts(1)
+    }
+
+    pub fn max_attempts(mut self, max_attempts: u32) -> RetryPolicy {
+        self.max_attempts = cmp::max(max_attempts, 1);
+        self
+    }
+
+    // The delay doubles after every attempt, up to `max`.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> RetryPolicy {
+        self.initial_backoff = initial;
+        self.max_backoff = cmp::max(initial, max);
+        self
+    }
+
+    // Every delay is shortened by a random part of up to `jitter` (0 to 1) of it.
+    pub fn jitter(mut self, jitter: f64) -> RetryPolicy {
+        self.jitter = if jitter.is_nan() {
+            0.0
+        } else {
+            jitter.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    pub fn retry_non_idempotent(mut self, retry: bool) -> RetryPolicy {
+        self.non_idempotent = retry;
+        self
+    }
+
+    pub fn retry_on<F>(mut self, retry_on: F) -> RetryPolicy
+    where
+        F: Fn(&PocketError) -> bool + Send + Sync + 'static,
+    {
+        self.retry_on = Arc::new(retry_on);
+        self
+    }
+
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> RetryPolicy {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    // A rate limit is only waited for if its reset time is known and no longer than `max_backoff`.
+    pub(crate) fn should_retry(&self, attempt: u32, idempotent: bool, err: &PocketError) -> bool {
+        let reset_soon = match *err {
+            PocketError::RateLimited { reset_in } => {
+                reset_in.map_or(false, |d| d <= self.max_backoff)
+            }
+            _ => true,
+        };
+        attempt < self.max_attempts
+            && (idempotent || self.non_idempotent)
+            && reset_soon
+            && (self.retry_on)(err)
+    }
+
+    // Delay before the attempt following the given one (counted from 1).
+    pub fn backoff_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |d| cmp::min(d, self.max_backoff));
+        let fraction = (random_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        delay.mul_f64(1.0 - self.jitter * fraction)
+    }
+
+    pub(crate) fn wait(&self, attempt: u32, err: &PocketError) {
+        match *err {
+            PocketError::RateLimited {
+                reset_in: Some(reset_in),
+            } => self.clock.sleep(reset_in),
+            _ => self.clock.sleep(self.backoff_for(attempt)),
+        }
+    }
+}
+
+// Connection failures, server errors, maintenance and rate limits.
+pub fn is_transient(err: &PocketError) -> bool {
+    match *err {
+        PocketError::Http(_) | PocketError::Io(_) | PocketError::RateLimited { .. } => true,
+        PocketError::Proto(ref err, _) => err.is_retryable(),
+        _ => false,
+    }
+}
+
+#[test]
+fn test_retry_policy() {
+    use crate::PocketApiError;
+
+    let policy = RetryPolicy::new()
+        .max_attempts(5)
+        .backoff(Duration::from_millis(100), Duration::from_millis(500))
+        .jitter(0.0);
+    let delays: Vec<_> = (1..6).map(|n| policy.backoff_for(n).as_millis()).collect();
+    assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+    assert_eq!(policy.backoff_for(100), Duration::from_millis(500));
+
+    let policy = policy.jitter(0.5);
+    for _ in 0..20 {
+        let delay = policy.backoff_for(3);
+        assert!(delay > Duration::from_millis(200) && delay <= Duration::from_millis(400));
+    }
+
+    let maintenance = PocketError::Proto(PocketApiError::Maintenance, String::new());
+    let bad_token = PocketError::Proto(PocketApiError::InvalidAccessToken, String::new());
+    assert!(policy.should_retry(4, true, &maintenance));
+    assert!(!policy.should_retry(5, true, &maintenance));
+    assert!(!policy.should_retry(1, false, &maintenance));
+    assert!(!policy.should_retry(1, true, &bad_token));
+
+    let rate_limited = |secs: Option<u64>| PocketError::RateLimited {
+        reset_in: secs.map(Duration::from_secs),
+    };
+    let policy = policy.backoff(Duration::from_millis(100), Duration::from_secs(60));
+    assert!(policy.should_retry(1, true, &rate_limited(Some(60))));
+    assert!(!policy.should_retry(1, true, &rate_limited(Some(61))));
+    assert!(!policy.should_retry(1, true, &rate_limited(None)));
+
+    let policy = policy
+        .retry_non_idempotent(true)
+        .retry_on(|err| match *err {
+            PocketError::Proto(PocketApiError::InvalidAccessToken, _) => true,
+            _ => false,
+        });
+    assert!(policy.should_retry(1, false, &bad_token));
+    assert!(!policy.should_retry(1, false, &maintenance));
+    assert!(!RetryPolicy::none().should_retry(1, true, &maintenance));
+}
@@ -0,0 +1,406 @@
+use crate::{
+    Pocket, PocketError, PocketGetRequest, PocketGetSort, PocketGetState, PocketGetTag,
+    PocketGetType, PocketItem, PocketItemHas, PocketResult, Query, Tag,
+};
+use std::mem;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone)]
+enum Filter {
+    Tagged(Tag),
+    NotTagged(Tag),
+    Untagged,
+    AnyTag,
+    Domain(String),
+    NotDomain(String),
+    NotType(PocketGetType),
+    NotWord(String),
+}
+
+impl Filter {
+    fn needs_tags(&self) -> bool {
+        match *self {
+            Filter::Tagged(_) | Filter::NotTagged(_) | Filter::Untagged | Filter::AnyTag => true,
+            _ => false,
+        }
+    }
+
+    fn matches(&self, item: &PocketItem) -> bool {
+        match *self {
+            Filter::Tagged(ref tag) => item.tags.iter().any(|t| t.tag == *tag),
+            Filter::NotTagged(ref tag) => item.tags.iter().all(|t| t.tag != *tag),
+            Filter::Untagged => item.tags.is_empty(),
+            Filter::AnyTag => !item.tags.is_empty(),
+            Filter::Domain(ref domain) => in_domain(item, domain),
+            Filter::NotDomain(ref domain) => !in_domain(item, domain),
+            Filter::NotType(content_type) => !is_type(item, content_type),
+            Filter::NotWord(ref word) => ![
+                &item.given_title,
+                &item.resolved_title,
+                &item.given_url,
+                &item.resolved_url,
+            ]
+            .iter()
+            .any(|s| s.to_lowercase().contains(&**word)),
+        }
+    }
+}
+
+pub(crate) fn url_host(url: &str) -> &str {
+    let rest = url.find("://").map(|i| &url[i + 3..]).unwrap_or(url);
+    let end = rest.find(&['/', ':', '?', '#'][..]);
+    &rest[..end.unwrap_or(rest.len())]
+}
+
+fn in_domain(item: &PocketItem, domain: &str) -> bool {
+    [&item.given_url, &item.resolved_url].iter().any(|url| {
+        let host = url_host(url).to_lowercase();
+        host == domain || host.ends_with(&*format!(".{}", domain))
+    })
+}
+
+const KEYS: [&str; 6] = ["tag", "is", "in", "type", "domain", "sort"];
+
+fn is_type(item: &PocketItem, content_type: PocketGetType) -> bool {
+    match content_type {
+        PocketGetType::Article => item.is_article,
+        PocketGetType::Video => item.has_video == PocketItemHas::Is,
+        PocketGetType::Image => item.has_image == PocketItemHas::Is,
+    }
+}
+
+struct Term {
+    negated: bool,
+    key: Option<String>,
+    value: String,
+}
+
+fn invalid(msg: String) -> PocketError {
+    PocketError::InvalidSearch(msg)
+}
+
+fn tokenize(expr: &str) -> PocketResult<Vec<Term>> {
+    let mut terms = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    loop {
+        while chars.peek().map_or(false, |c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let negated = chars.peek() == Some(&'-');
+        if negated {
+            chars.next();
+        }
+
+        let mut key = None;
+        let mut value = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            chars.next();
+            match c {
+                '"' => loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => return Err(invalid(format!("unterminated quote in `{}`", expr))),
+                    }
+                },
+                ':' if key.is_none() && !value.is_empty() => {
+                    key = Some(mem::replace(&mut value, String::new()));
+                }
+                _ => value.push(c),
+            }
+        }
+
+        if value.is_empty() && key.is_none() {
+            return Err(invalid("empty search term".to_string()));
+        }
+
+        terms.push(Term {
+            negated: negated,
+            key: key,
+            value: value,
+        });
+    }
+
+    Ok(terms)
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Search {
+    query: Query,
+    filters: Vec<Filter>,
+}
+
+impl Search {
+    pub fn parse(expr: &str) -> PocketResult<Search> {
+        let mut search = Search::default();
+        let mut words = Vec::new();
+        for term in tokenize(expr)? {
+            search.apply(term, &mut words)?;
+        }
+
+        if !words.is_empty() {
+            search.query.search(&*words.join(" "));
+        }
+        // tags are only returned with complete details
+        if search.filters.iter().any(Filter::needs_tags) {
+            search.query.complete();
+        }
+        Ok(search)
+    }
+
+    #[inline]
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    pub fn has_client_filters(&self) -> bool {
+        !self.filters.is_empty()
+    }
+
+    pub fn matches(&self, item: &PocketItem) -> bool {
+        self.filters.iter().all(|f| f.matches(item))
+    }
+
+    pub fn request<'a>(&self, pocket: &'a mut Pocket) -> PocketGetRequest<'a> {
+        pocket.filter_with(self.query.clone())
+    }
+
+    fn apply(&mut self, term: Term, words: &mut Vec<String>) -> PocketResult<()> {
+        let Term {
+            negated,
+            key,
+            value,
+        } = term;
+        let key = match key {
+            Some(ref key) if KEYS.contains(&&*key.to_lowercase()) => key.to_lowercase(),
+            // anything else with a colon, e.g. a pasted URL, is plain text
+            Some(key) => {
+                self.word(format!("{}:{}", key, value), negated, words);
+                return Ok(());
+            }
+            None => {
+                self.word(value, negated, words);
+                return Ok(());
+            }
+        };
+        if value.is_empty() {
+            return Err(invalid(format!("missing value for `{}:`", key)));
+        }
+
+        match (&*key, &*value.to_lowercase(), negated) {
+            ("tag", "_untagged_", false) | ("is", "untagged", false) => self.untagged(),
+            ("tag", "_untagged_", true) | ("is", "untagged", true) => {
+                self.filters.push(Filter::AnyTag)
+            }
+            ("tag", _, false) => {
+                let tag = Tag::new(&*value)?;
+                if self.query.tag.is_none() {
+                    self.query.tag(PocketGetTag::Tagged(tag));
+                } else {
+                    self.filters.push(Filter::Tagged(tag));
+                }
+            }
+            ("tag", _, true) => self.filters.push(Filter::NotTagged(Tag::new(&*value)?)),
+            ("is", "unread", false) | ("is", "archived", true) => {
+                self.state(PocketGetState::Unread)?
+            }
+            ("is", "archived", false) | ("is", "unread", true) => {
+                self.state(PocketGetState::Archive)?
+            }
+            ("is", "favorite", negated) | ("is", "starred", negated) => self.favorite(!negated)?,
+            ("is", "article", false) | ("type", "article", false) => {
+                self.content_type(PocketGetType::Article)?
+            }
+            ("is", "video", false) | ("type", "video", false) => {
+                self.content_type(PocketGetType::Video)?
+            }
+            ("is", "image", false) | ("type", "image", false) => {
+                self.content_type(PocketGetType::Image)?
+            }
+            ("is", "article", true) | ("type", "article", true) => {
+                self.filters.push(Filter::NotType(PocketGetType::Article))
+            }
+            ("is", "video", true) | ("type", "video", true) => {
+                self.filters.push(Filter::NotType(PocketGetType::Video))
+            }
+            ("is", "image", true) | ("type", "image", true) => {
+                self.filters.push(Filter::NotType(PocketGetType::Image))
+            }
+            ("in", "all", false) => self.state(PocketGetState::All)?,
+            ("domain", domain, false) => {
+                if self.query.domain.is_none() {
+                    self.query.domain(domain);
+                } else {
+                    self.filters.push(Filter::Domain(domain.to_string()));
+                }
+            }
+            ("domain", domain, true) => self.filters.push(Filter::NotDomain(domain.to_string())),
+            ("sort", "newest", false) => self.sort(PocketGetSort::Newest)?,
+            ("sort", "oldest", false) => self.sort(PocketGetSort::Oldest)?,
+            ("sort", "title", false) => self.sort(PocketGetSort::Title)?,
+            ("sort", "site", false) => self.sort(PocketGetSort::Site)?,
+            (key, _, _) => {
+                return Err(invalid(format!(
+                    "unknown search term `{}{}:{}`",
+                    if negated { "-" } else { "" },
+                    key,
+                    value
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn word(&mut self, word: String, negated: bool, words: &mut Vec<String>) {
+        if negated {
+            self.filters.push(Filter::NotWord(word.to_lowercase()));
+        } else {
+            words.push(word);
+        }
+    }
+
+    fn untagged(&mut self) {
+        if self.query.tag.is_none() {
+            self.query.tag(PocketGetTag::Untagged);
+        } else {
+            self.filters.push(Filter::Untagged);
+        }
+    }
+
+    fn state(&mut self, state: PocketGetState) -> PocketResult<()> {
+        match self.query.state {
+            Some(s) if s != state => Err(invalid(format!(
+                "conflicting states `{:?}` and `{:?}`",
+                s, state
+            ))),
+            _ => {
+                self.query.state(state);
+                Ok(())
+            }
+        }
+    }
+
+    fn favorite(&mut self, favorite: bool) -> PocketResult<()> {
+        match self.query.favorite {
+            Some(f) if f != favorite => Err(invalid(
+                "conflicting `is:favorite` and `-is:favorite`".to_string(),
+            )),
+            _ => {
+                self.query.favorite(favorite);
+                Ok(())
+            }
+        }
+    }
+
+    fn content_type(&mut self, content_type: PocketGetType) -> PocketResult<()> {
+        match self.query.content_type {
+            Some(t) if t != content_type => Err(invalid(format!(
+                "conflicting types `{:?}` and `{:?}`",
+                t, content_type
+            ))),
+            _ => {
+                self.query.content_type(content_type);
+                Ok(())
+            }
+        }
+    }
+
+    fn sort(&mut self, sort: PocketGetSort) -> PocketResult<()> {
+        match self.query.sort {
+            Some(s) if s != sort => Err(invalid(format!(
+                "conflicting sort orders `{:?}` and `{:?}`",
+                s, sort
+            ))),
+            _ => {
+                self.query.sort(sort);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Search {
+    type Err = PocketError;
+
+    fn from_str(s: &str) -> PocketResult<Search> {
+        Search::parse(s)
+    }
+}
+
+#[test]
+fn test_search_parse() {
+    let search =
+        Search::parse(r#"tag:rust is:unread domain:github.com sort:oldest "async io""#).unwrap();
+    let mut query = Query::new();
+    query
+        .tag(PocketGetTag::Tagged(Tag::new("rust").unwrap()))
+        .unread()
+        .domain("github.com")
+        .sort_by_oldest()
+        .search("async io");
+    assert_eq!(*search.query(), query);
+    assert!(!search.has_client_filters());
+
+    let search = Search::parse("tag:rust tag:tokio -tag:read-later -is:favorite").unwrap();
+    assert_eq!(
+        search.query().tag,
+        Some(PocketGetTag::Tagged(Tag::new("rust").unwrap()))
+    );
+    assert_eq!(search.query().favorite, Some(false));
+    assert_eq!(
+        search.query().detail_type,
+        Some(crate::PocketGetDetail::Complete)
+    );
+    assert_eq!(
+        search.filters,
+        vec![
+            Filter::Tagged(Tag::new("tokio").unwrap()),
+            Filter::NotTagged(Tag::new("read-later").unwrap()),
+        ]
+    );
+
+    let search = Search::parse("https://example.com/a?b=c color:red -Note:x").unwrap();
+    assert_eq!(
+        search.query().search,
+        Some("https://example.com/a?b=c color:red".to_string())
+    );
+    assert_eq!(search.filters, vec![Filter::NotWord("note:x".to_string())]);
+
+    assert!(Search::parse("is:unread is:archived").is_err());
+    assert!(Search::parse("is:favorite -is:starred").is_err());
+    assert!(Search::parse("is:favorite is:starred").is_ok());
+    assert!(Search::parse("sort:random").is_err());
+    assert!(Search::parse(r#""unterminated"#).is_err());
+    assert!(Search::parse("tag:").is_err());
+}
+
+#[test]
+fn test_search_matches() {
+    let item: PocketItem = rustc_serialize::json::decode(crate::ITEM_JSON).unwrap();
+
+    assert!(Search::parse("tag:rust tag:async").unwrap().matches(&item));
+    assert!(!Search::parse("tag:rust tag:tokio").unwrap().matches(&item));
+    assert!(!Search::parse("-tag:async").unwrap().matches(&item));
+    assert!(Search::parse("-is:untagged").unwrap().matches(&item));
+    assert!(Search::parse("-domain:github.com").unwrap().matches(&item));
+    assert!(!Search::parse("-example").unwrap().matches(&item));
+
+    let search = Search::parse("-is:video -type:image").unwrap();
+    assert!(search.has_client_filters());
+    assert!(search.matches(&item));
+    assert!(!Search::parse("-is:article").unwrap().matches(&item));
+
+    let mut video: PocketItem = rustc_serialize::json::decode(crate::ITEM_JSON).unwrap();
+    video.has_video = PocketItemHas::Is;
+    video.is_article = false;
+    assert!(!Search::parse("-is:video").unwrap().matches(&video));
+    assert!(Search::parse("-type:article").unwrap().matches(&video));
+}
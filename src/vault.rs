@@ -0,0 +1,118 @@
+use crate::{PocketError, PocketResult};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rustc_serialize::base64::{FromBase64, ToBase64, STANDARD};
+use rustc_serialize::json;
+
+const VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+// Refuse to derive with costs from a file that would take too long or too
+// much memory (m_cost is in KiB).
+const MAX_M_COST: u32 = 1 << 20;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 16;
+
+#[derive(RustcEncodable, RustcDecodable)]
+struct VaultFile {
+    version: u32,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+// A passphrase-derived key (Argon2id) with the salt and cost parameters
+// needed to derive it again.
+pub(crate) struct VaultKey {
+    salt: Vec<u8>,
+    params: Params,
+    key: [u8; 32],
+}
+
+fn vault_error<E: std::fmt::Display>(err: E) -> PocketError {
+    PocketError::Vault(err.to_string())
+}
+
+fn random_bytes(len: usize) -> PocketResult<Vec<u8>> {
+    let mut buf = vec![0; len];
+    getrandom::getrandom(&mut buf).map_err(vault_error)?;
+    Ok(buf)
+}
+
+fn decode_base64(field: &str, value: &str) -> PocketResult<Vec<u8>> {
+    value
+        .from_base64()
+        .map_err(|e| PocketError::Vault(format!("invalid {}: {}", field, e)))
+}
+
+impl VaultKey {
+    pub(crate) fn new(passphrase: &str) -> PocketResult<VaultKey> {
+        VaultKey::derive(passphrase, random_bytes(SALT_LEN)?, Params::default())
+    }
+
+    fn derive(passphrase: &str, salt: Vec<u8>, params: Params) -> PocketResult<VaultKey> {
+        let mut key = [0; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(vault_error)?;
+        Ok(VaultKey {
+            salt: salt,
+            params: params,
+            key: key,
+        })
+    }
+
+    // Every seal uses a fresh random nonce.
+    pub(crate) fn seal(&self, plaintext: &[u8]) -> PocketResult<String> {
+        let nonce = random_bytes(NONCE_LEN)?;
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&self.key))
+            .encrypt(XNonce::from_slice(&nonce), plaintext)
+            .map_err(vault_error)?;
+
+        json::encode(&VaultFile {
+            version: VERSION,
+            m_cost: self.params.m_cost(),
+            t_cost: self.params.t_cost(),
+            p_cost: self.params.p_cost(),
+            salt: self.salt.to_base64(STANDARD),
+            nonce: nonce.to_base64(STANDARD),
+            ciphertext: ciphertext.to_base64(STANDARD),
+        })
+        .map_err(From::from)
+    }
+
+    pub(crate) fn unseal(data: &str, passphrase: &str) -> PocketResult<(VaultKey, Vec<u8>)> {
+        let file: VaultFile = json::decode(data)
+            .map_err(|_| PocketError::Vault("not an encrypted store".to_string()))?;
+        if file.version != VERSION {
+            return Err(PocketError::Vault(format!(
+                "unsupported vault version {}",
+                file.version
+            )));
+        }
+
+        if file.m_cost > MAX_M_COST || file.t_cost > MAX_T_COST || file.p_cost > MAX_P_COST {
+            return Err(PocketError::Vault(
+                "vault parameters out of range".to_string(),
+            ));
+        }
+        let params =
+            Params::new(file.m_cost, file.t_cost, file.p_cost, None).map_err(vault_error)?;
+        let key = VaultKey::derive(passphrase, decode_base64("salt", &*file.salt)?, params)?;
+        let nonce = decode_base64("nonce", &*file.nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(PocketError::Vault("invalid nonce length".to_string()));
+        }
+        let ciphertext = decode_base64("ciphertext", &*file.ciphertext)?;
+
+        // the tag check fails both for a wrong passphrase and a tampered file
+        let plaintext = XChaCha20Poly1305::new(Key::from_slice(&key.key))
+            .decrypt(XNonce::from_slice(&nonce), &*ciphertext)
+            .map_err(|_| PocketError::WrongPassphrase)?;
+        Ok((key, plaintext))
+    }
+}
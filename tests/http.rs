@@ -0,0 +1,435 @@
+extern crate pocket;
+
+use pocket::{
+    ActionOutcome, Clock, Pocket, PocketAction, PocketApiError, PocketArchiveAction, PocketAuth,
+    PocketError, PocketGetTag, Query, RetryPolicy, SendStatus, Tag, Throttle,
+};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// A stand-in for the Pocket API: answers the given responses in order
+// and returns the requests it got.
+fn stand_in(responses: Vec<String>) -> (String, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        responses
+            .into_iter()
+            .map(|response| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.to_lowercase().starts_with("content-length:") {
+                        length = line[15..].trim().parse().unwrap();
+                    }
+                    request.push_str(&*line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&*String::from_utf8(body).unwrap());
+
+                stream.write_all(response.as_bytes()).unwrap();
+                request
+            })
+            .collect()
+    });
+    (base_url, handle)
+}
+
+fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let headers: String = headers
+        .iter()
+        .map(|&(name, value)| format!("{}: {}\r\n", name, value))
+        .collect();
+    format!(
+        "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    )
+}
+
+#[test]
+fn test_request_headers() {
+    let (base_url, server) = stand_in(vec![response(
+        "200 OK",
+        &[("Content-Type", "application/json")],
+        r#"{"status": 1, "complete": 1, "list": [], "since": 1500000000}"#,
+    )]);
+    let mut pocket = Pocket::new("key", "token");
+    pocket.set_base_url(&*base_url);
+    assert!(pocket.get(&Query::new()).unwrap().is_empty());
+
+    let requests = server.join().unwrap();
+    let request = requests[0].to_lowercase();
+    assert!(request.starts_with("post /v3/get "));
+    assert!(request.contains("\r\nx-accept: application/json\r\n"));
+    assert!(request.contains("\r\ncontent-type: application/json"));
+    assert!(request.contains(r#"{"consumer_key":"key","access_token":"token""#));
+}
+
+#[test]
+fn test_error_headers() {
+    let (base_url, server) = stand_in(vec![response(
+        "401 Unauthorized",
+        &[("X-Error-Code", "107"), ("X-Error", "Invalid access token")],
+        "",
+    )]);
+    let mut pocket = Pocket::new("key", "expired");
+    pocket.set_base_url(&*base_url);
+    match pocket.push("http://example.com") {
+        Err(PocketError::Proto(PocketApiError::InvalidAccessToken, ref msg)) => {
+            assert_eq!(msg, "Invalid access token")
+        }
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    server.join().unwrap();
+
+    let (base_url, server) = stand_in(vec![
+        response(
+            "404 Not Found",
+            &[("Content-Type", "text/html")],
+            "<html></html>",
+        ),
+        response(
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            r#"{"status": 1, "action_results": [false]}"#,
+        ),
+    ]);
+    pocket.set_base_url(&*base_url);
+    match pocket.push("http://example.com") {
+        Err(PocketError::Proto(PocketApiError::Status(404), _)) => (),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    // a failed action without an error code
+    match pocket.clear_tags(1) {
+        Err(PocketError::Proto(err, _)) => {
+            assert_eq!(err, PocketApiError::ActionFailed);
+            assert_eq!(err.code(), None);
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn test_form_encoded_oauth() {
+    let form = [("Content-Type", "application/x-www-form-urlencoded")];
+    let (base_url, server) = stand_in(vec![
+        response("200 OK", &form, "code=abc-123&state=xyz"),
+        response(
+            "200 OK",
+            &form,
+            "access_token=t0k3n&username=jane%40example.com&state=xyz",
+        ),
+        response(
+            "200 OK",
+            &form,
+            "access_token=t0k3n&username=x&state=forged",
+        ),
+    ]);
+    let auth = PocketAuth::new("key").base_url(&*base_url);
+    let pending = auth.request_authorization_with_state("xyz").unwrap();
+    assert_eq!(pending.request_token, "abc-123");
+    // the state comes back to the redirect handler with the redirect URI
+    assert!(pending
+        .auth_url
+        .to_string()
+        .ends_with("redirect_uri=rustapi%3Afinishauth%3Fstate%3Dxyz"));
+
+    let (pocket, username) = auth.authorize(&pending).unwrap();
+    assert_eq!(pocket.access_token(), "t0k3n");
+    assert_eq!(pocket.base_url(), base_url);
+    assert_eq!(username, "jane@example.com");
+    match auth.authorize(&pending) {
+        Err(PocketError::StateMismatch) => (),
+        r => panic!("unexpected result: {:?}", r.map(|v| v.1)),
+    }
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("POST /v3/oauth/request "));
+    assert!(requests[0].contains(r#""redirect_uri":"rustapi:finishauth?state=xyz","state":"xyz""#));
+    assert!(requests[1].ends_with(r#"{"consumer_key":"key","code":"abc-123"}"#));
+}
+
+#[test]
+fn test_rate_limits() {
+    let (base_url, server) = stand_in(vec![response(
+        "200 OK",
+        &[
+            ("Content-Type", "application/json"),
+            ("X-Limit-User-Limit", "320"),
+            ("X-Limit-User-Remaining", "0"),
+            ("X-Limit-User-Reset", "600"),
+        ],
+        r#"{"status": 1, "complete": 1, "list": [], "since": 1500000000}"#,
+    )]);
+    let mut pocket = Pocket::new("key", "token");
+    pocket.set_base_url(&*base_url);
+    pocket.set_throttle(Throttle::Fail);
+    assert!(pocket.rate_limits().is_none());
+    pocket.get(&Query::new()).unwrap();
+
+    let user = pocket.rate_limits().unwrap().user.unwrap();
+    assert_eq!((user.limit, user.remaining), (Some(320), 0));
+    assert!(pocket.rate_limits().unwrap().key.is_none());
+
+    // the stand-in is gone, so the request must not be sent
+    server.join().unwrap();
+    match pocket.get(&Query::new()) {
+        Err(PocketError::RateLimited {
+            reset_in: Some(reset_in),
+        }) => {
+            assert!(reset_in > Duration::from_secs(590) && reset_in <= Duration::from_secs(600))
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+// Records the delays instead of sleeping.
+#[derive(Clone, Default)]
+struct TestClock(Arc<Mutex<Vec<Duration>>>);
+
+impl Clock for TestClock {
+    fn sleep(&self, duration: Duration) {
+        self.0.lock().unwrap().push(duration);
+    }
+}
+
+#[test]
+fn test_retry_policy() {
+    let unavailable = || response("503 Service Unavailable", &[], "");
+    let (base_url, server) = stand_in(vec![
+        unavailable(),
+        unavailable(),
+        response(
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            r#"{"status": 1, "complete": 1, "list": [], "since": 1500000000}"#,
+        ),
+        unavailable(),
+        unavailable(),
+        unavailable(),
+    ]);
+    let clock = TestClock::default();
+    let mut pocket = Pocket::new("key", "token");
+    pocket.set_base_url(&*base_url);
+    pocket.set_retry_policy(
+        RetryPolicy::new()
+            .max_attempts(3)
+            .backoff(Duration::from_millis(100), Duration::from_secs(1))
+            .jitter(0.0)
+            .clock(clock.clone()),
+    );
+    assert!(pocket.get(&Query::new()).unwrap().is_empty());
+    assert_eq!(
+        *clock.0.lock().unwrap(),
+        vec![Duration::from_millis(100), Duration::from_millis(200)]
+    );
+
+    // `add` isn't retried unless asked for
+    let not_retried = pocket.push("http://example.com");
+    let retry = pocket.retry_policy().clone().retry_non_idempotent(true);
+    pocket.set_retry_policy(retry.max_attempts(2));
+    let retried = pocket.push("http://example.com");
+    for result in vec![not_retried, retried] {
+        match result {
+            Err(PocketError::Proto(PocketApiError::Maintenance, _)) => (),
+            r => panic!("unexpected result: {:?}", r.map(|_| ())),
+        }
+    }
+    assert_eq!(clock.0.lock().unwrap().len(), 3);
+    assert_eq!(server.join().unwrap().len(), 6);
+}
+
+#[test]
+fn test_retry_rate_limited() {
+    let rate_limited = |reset| {
+        response(
+            "429 Too Many Requests",
+            &[
+                ("X-Limit-User-Remaining", "0"),
+                ("X-Limit-User-Reset", reset),
+            ],
+            "",
+        )
+    };
+    let (base_url, server) = stand_in(vec![
+        rate_limited("1"),
+        response(
+            "200 OK",
+            &[("Content-Type", "application/json")],
+            r#"{"status": 1, "complete": 1, "list": [], "since": 1500000000}"#,
+        ),
+        rate_limited("600"),
+    ]);
+    let clock = TestClock::default();
+    let mut pocket = Pocket::new("key", "token");
+    pocket.set_base_url(&*base_url);
+    pocket.set_retry_policy(
+        RetryPolicy::new()
+            .backoff(Duration::from_millis(100), Duration::from_secs(30))
+            .jitter(0.0)
+            .clock(clock.clone()),
+    );
+    assert!(pocket.get(&Query::new()).unwrap().is_empty());
+    assert_eq!(*clock.0.lock().unwrap(), vec![Duration::from_secs(1)]);
+
+    // not worth waiting for longer than the maximum backoff
+    match pocket.get(&Query::new()) {
+        Err(PocketError::RateLimited {
+            reset_in: Some(reset_in),
+        }) => assert_eq!(reset_in, Duration::from_secs(600)),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    assert_eq!(clock.0.lock().unwrap().len(), 1);
+    assert_eq!(server.join().unwrap().len(), 3);
+}
+
+#[test]
+fn test_send_all() {
+    let json = [("Content-Type", "application/json")];
+    let (base_url, server) = stand_in(vec![
+        response(
+            "200 OK",
+            &json,
+            r#"{"status": 1, "action_results": [true, false],
+                "action_errors": [null, {"message": "Invalid item id", "code": 422}]}"#,
+        ),
+        response(
+            "401 Unauthorized",
+            &[("X-Error-Code", "107"), ("X-Error", "Invalid access token")],
+            "",
+        ),
+    ]);
+    let mut pocket = Pocket::new("key", "token");
+    pocket.set_base_url(&*base_url);
+    let archived: Vec<_> = (1..6).map(PocketArchiveAction::new).collect();
+    let actions: Vec<&dyn PocketAction> = archived.iter().map(|a| a as &dyn PocketAction).collect();
+
+    let report = pocket.send_all(&*actions, 2);
+    assert!(!report.is_success());
+    assert_eq!(
+        report.statuses,
+        vec![
+            SendStatus::Sent(ActionOutcome::Success),
+            SendStatus::Sent(ActionOutcome::Failure {
+                code: Some(422),
+                message: "Invalid item id".to_string(),
+            }),
+            SendStatus::Failed,
+            SendStatus::Failed,
+            SendStatus::NotAttempted,
+        ]
+    );
+    match report.error {
+        Some(PocketError::Proto(PocketApiError::InvalidAccessToken, _)) => (),
+        r => panic!("unexpected error: {:?}", r),
+    }
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].contains(r#""item_id":3"#) && requests[1].contains(r#""item_id":4"#));
+    assert!(!requests[1].contains(r#""item_id":5"#));
+
+    // a zero chunk size sends the actions one by one
+    let (base_url, server) = stand_in(vec![
+        response(
+            "200 OK",
+            &json,
+            r#"{"status": 1, "action_results": [true]}"#,
+        ),
+        response(
+            "200 OK",
+            &json,
+            r#"{"status": 1, "action_results": [true]}"#,
+        ),
+    ]);
+    pocket.set_base_url(&*base_url);
+    let report = pocket.send_all(&actions[..2], 0);
+    assert!(report.is_success());
+    assert_eq!(report.statuses.len(), 2);
+    assert_eq!(server.join().unwrap().len(), 2);
+}
+
+fn get_response(items: &[(u64, usize)]) -> String {
+    let list: Vec<String> = items
+        .iter()
+        .map(|&(id, sort_id)| {
+            format!(
+                r#""{id}": {{"item_id": "{id}", "resolved_id": "{id}", "given_url": "http://example.com/{id}",
+                    "given_title": "", "favorite": "0", "status": "0", "time_added": "1500000000",
+                    "time_updated": "1500000000", "time_read": "0", "time_favorited": "0",
+                    "sort_id": {sort_id}, "resolved_title": "", "resolved_url": "http://example.com/{id}",
+                    "excerpt": "", "is_article": "1", "is_index": "0", "has_video": "0",
+                    "has_image": "0", "word_count": "0"}}"#,
+                id = id,
+                sort_id = sort_id
+            )
+        })
+        .collect();
+    let body = format!(
+        r#"{{"status": 1, "complete": 1, "since": 1500000000, "list": {{{}}}}}"#,
+        list.join(",")
+    );
+    response("200 OK", &[("Content-Type", "application/json")], &*body)
+}
+
+#[test]
+fn test_pages() {
+    let (base_url, server) = stand_in(vec![
+        get_response(&[(10, 1), (20, 0)]),
+        get_response(&[(30, 0), (40, 1)]),
+        get_response(&[]),
+    ]);
+    let mut pocket = Pocket::new("key", "token");
+    pocket.set_base_url(&*base_url);
+    let mut request = pocket.filter();
+    request
+        .unread()
+        .tag(PocketGetTag::Tagged(Tag::new("rust").unwrap()));
+    let pages: Vec<Vec<u64>> = request
+        .pages(2)
+        .map(|page| page.unwrap().iter().map(|item| item.item_id).collect())
+        .collect();
+    assert_eq!(pages, vec![vec![20, 10], vec![30, 40]]);
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 3);
+    for (n, request) in requests.iter().enumerate() {
+        assert!(request.contains(r#""tag":"rust","state":"unread""#));
+        assert!(request.contains(&*format!(r#""count":2,"offset":{}"#, n * 2)));
+    }
+}
+
+#[test]
+fn test_iter_short_page() {
+    let (base_url, server) = stand_in(vec![
+        get_response(&[(1, 0), (2, 1), (3, 2)]),
+        get_response(&[(4, 0)]),
+    ]);
+    let mut pocket = Pocket::new("key", "token");
+    pocket.set_base_url(&*base_url);
+    let mut request = pocket.filter();
+    request.archived().count(3);
+    let ids: Vec<u64> = request.iter().map(|item| item.unwrap().item_id).collect();
+    assert_eq!(ids, vec![1, 2, 3, 4]);
+
+    // the short page is the last one, no request for a third
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].contains(r#""state":"archive""#));
+    assert!(requests[1].contains(r#""count":3,"offset":3"#));
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "loopback")]
+
+extern crate pocket;
+
+use pocket::LoopbackServer;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+// Sends a GET request the way a browser following the redirect would.
+fn browse(addr: SocketAddr, target: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: fake-browser\r\n\r\n",
+        target, addr
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_loopback_redirect() {
+    let server = LoopbackServer::bind()
+        .unwrap()
+        .timeout(Duration::from_secs(10));
+    let addr = server.local_addr().unwrap();
+    let redirect_uri = server.redirect_uri().unwrap();
+    assert!(redirect_uri.starts_with(&*format!("http://{}/pocket/callback?state=", addr)));
+    let target = redirect_uri[redirect_uri.find("/pocket").unwrap()..].to_string();
+
+    let browser = thread::spawn(move || {
+        (
+            browse(addr, "/favicon.ico"),
+            browse(addr, "/pocket/callback?state=forged"),
+            browse(addr, &*target),
+        )
+    });
+    server.wait().unwrap();
+
+    let (favicon, forged, callback) = browser.join().unwrap();
+    assert!(favicon.starts_with("HTTP/1.1 404"));
+    assert!(forged.starts_with("HTTP/1.1 404"));
+    assert!(callback.starts_with("HTTP/1.1 200"));
+}
+
+#[test]
+fn test_loopback_timeout() {
+    let server = LoopbackServer::bind()
+        .unwrap()
+        .timeout(Duration::from_millis(100));
+    assert!(server.wait().is_err());
+}